@@ -1,5 +1,9 @@
-use std::collections::HashMap;
-use std::net::{IpAddr, Ipv4Addr};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{Read as _, Write as _};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
 use tokio::sync::RwLock;
@@ -9,7 +13,16 @@ use trust_dns_proto::op::{
 };
 use trust_dns_proto::rr::{
     dns_class::DNSClass, rdata, record_data::RData, record_type::RecordType, resource::Record,
+    Name,
 };
+use trust_dns_proto::rr::rdata::svcb::{IpHint, SvcParamKey, SvcParamValue, SVCB};
+
+use crate::app::trie::TrieNode;
+use crate::app::upstream_resolver::UpstreamResolver;
+
+// ULA prefix used for the IPv6 fake-address pool: fc00::/18, i.e. the top
+// 18 bits are fixed and the cursor walks the remaining 110 bits.
+const FAKE_IPV6_PREFIX: [u8; 2] = [0xfc, 0x00];
 
 #[derive(Debug)]
 pub enum FakeDnsMode {
@@ -17,89 +30,357 @@ pub enum FakeDnsMode {
     Exclude,
 }
 
-pub struct FakeDns(RwLock<FakeDnsImpl>);
+pub struct FakeDns {
+    inner: RwLock<FakeDnsImpl>,
+    // Handles domains `accept()` rejects, so FakeDns can act as a complete
+    // split DNS server rather than a fake-only responder.
+    resolver: Option<Arc<dyn UpstreamResolver>>,
+}
 
 impl FakeDns {
     pub fn new(mode: FakeDnsMode) -> Self {
-        Self(RwLock::new(FakeDnsImpl::new(mode)))
+        Self::with_store(mode, None)
+    }
+
+    /// Like [`FakeDns::new`], but snapshots the domain<->IP mapping table to
+    /// `store_path` on [`FakeDns::flush`] and reloads it here, so a domain
+    /// gets the same fake IP across process restarts.
+    pub fn with_store(mode: FakeDnsMode, store_path: Option<PathBuf>) -> Self {
+        let mut inner = FakeDnsImpl::new(mode);
+        inner.store_path = store_path;
+        if let Err(e) = inner.load_store() {
+            info!("[FakeDNS] 未能从磁盘恢复映射表，使用空表启动: {}", e);
+        }
+        Self {
+            inner: RwLock::new(inner),
+            resolver: None,
+        }
+    }
+
+    /// Attaches an [`UpstreamResolver`] used to answer domains that
+    /// `accept()` rejects, instead of erroring out on them.
+    pub fn set_resolver(&mut self, resolver: Arc<dyn UpstreamResolver>) {
+        self.resolver = Some(resolver);
     }
 
     pub async fn add_filter(&self, filter: String) {
-        self.0.write().await.add_filter(filter)
+        self.inner.write().await.add_filter(filter)
+    }
+
+    /// Adds a suffix rule: matches `domain` and all of its subdomains.
+    pub async fn add_suffix(&self, domain: &str) {
+        self.inner.write().await.add_suffix(domain)
+    }
+
+    /// Adds an exact rule: matches `domain` itself only.
+    pub async fn add_exact(&self, domain: &str) {
+        self.inner.write().await.add_exact(domain)
+    }
+
+    /// Snapshot the current mapping table and cursors to the configured
+    /// store path, if any. Call this periodically (e.g. from a `tokio`
+    /// interval) and on graceful shutdown.
+    pub async fn flush(&self) -> Result<()> {
+        self.inner.read().await.flush_store()
     }
 
     pub async fn query_domain(&self, ip: &IpAddr) -> Option<String> {
-        self.0.read().await.query_domain(ip)
+        self.inner.read().await.query_domain(ip)
     }
 
     pub async fn query_fake_ip(&self, domain: &str) -> Option<IpAddr> {
-        self.0.read().await.query_fake_ip(domain)
+        self.inner.write().await.query_fake_ip(domain)
+    }
+
+    pub async fn query_fake_ipv6(&self, domain: &str) -> Option<IpAddr> {
+        self.inner.read().await.query_fake_ipv6(domain)
     }
 
     pub async fn generate_fake_response(&self, request: &[u8]) -> Result<Vec<u8>> {
-        self.0.write().await.generate_fake_response(request)
+        match self.inner.write().await.generate_fake_response(request)? {
+            FakeDnsOutcome::Answer(bytes) => Ok(bytes),
+            FakeDnsOutcome::Forward => {
+                let resolver = self.resolver.clone().ok_or_else(|| {
+                    anyhow!("domain not accepted by FakeDns and no upstream resolver configured")
+                })?;
+                resolver.resolve(request).await
+            }
+        }
     }
 
     pub async fn is_fake_ip(&self, ip: &IpAddr) -> bool {
-        self.0.read().await.is_fake_ip(ip)
+        self.inner.read().await.is_fake_ip(ip)
     }
 }
 
+/// Outcome of [`FakeDnsImpl::generate_fake_response`]: either a ready-to-send
+/// fake answer, or a signal that the domain was rejected by `accept()` and
+/// should be forwarded to an upstream resolver instead.
+enum FakeDnsOutcome {
+    Answer(Vec<u8>),
+    Forward,
+}
+
 struct FakeDnsImpl {
     ip_to_domain: HashMap<u32, String>,
     domain_to_ip: HashMap<String, u32>,
     cursor: u32,
     min_cursor: u32,
     max_cursor: u32,
+    // Number of usable (non .0/.255) addresses in [min_cursor, max_cursor].
+    capacity: usize,
+    // Access-ordered list of allocated domains, front = least-recently-used.
+    // `allocate_ip` only evicts from here once the pool is full.
+    lru_order: VecDeque<String>,
+    ipv6_to_domain: HashMap<u128, String>,
+    domain_to_ipv6: HashMap<String, u128>,
+    cursor6: u128,
+    min_cursor6: u128,
+    max_cursor6: u128,
     ttl: u32,
-    filters: Vec<String>,
+    // Suffix ("+.example.com"/"domain:example.com") and exact filter rules.
+    filter_trie: TrieNode,
+    // A "*" filter accepts/excludes every domain regardless of the trie.
+    filter_wildcard: bool,
     mode: FakeDnsMode,
+    store_path: Option<PathBuf>,
 }
 
 impl FakeDnsImpl {
     pub(self) fn new(mode: FakeDnsMode) -> Self {
         let min_cursor = Self::ip_to_u32(&Ipv4Addr::new(198, 18, 0, 0));
         let max_cursor = Self::ip_to_u32(&Ipv4Addr::new(198, 18, 255, 255));
-        info!("[FakeDNS] 初始化 | 模式: {:?} | IP范围: 198.18.0.0 - 198.18.255.255", mode);
+        let min_cursor6 = Self::ipv6_to_u128(&Self::ipv6_base());
+        let max_cursor6 = min_cursor6 | (u128::MAX >> 18);
+        // Every /24 block loses its .0 and .255 host addresses.
+        let capacity = ((max_cursor - min_cursor + 1) / 256 * 254) as usize;
+        info!(
+            "[FakeDNS] 初始化 | 模式: {:?} | IPv4范围: 198.18.0.0 - 198.18.255.255 | IPv6范围: {}/18",
+            mode,
+            Self::ipv6_base()
+        );
         Self {
             ip_to_domain: HashMap::new(),
             domain_to_ip: HashMap::new(),
             cursor: min_cursor,
             min_cursor,
             max_cursor,
+            capacity,
+            lru_order: VecDeque::new(),
+            ipv6_to_domain: HashMap::new(),
+            domain_to_ipv6: HashMap::new(),
+            cursor6: min_cursor6,
+            min_cursor6,
+            max_cursor6,
             ttl: 1,
-            filters: Vec::new(),
+            filter_trie: TrieNode::new(),
+            filter_wildcard: false,
             mode,
+            store_path: None,
         }
     }
 
     pub(self) fn add_filter(&mut self, filter: String) {
         info!("[FakeDNS] 添加过滤规则: {}", filter);
-        self.filters.push(filter);
+        if filter == "*" {
+            self.filter_wildcard = true;
+        } else if let Some(suffix) = filter.strip_prefix("+.") {
+            self.add_suffix(suffix);
+        } else if let Some(suffix) = filter.strip_prefix("domain:") {
+            self.add_suffix(suffix);
+        } else {
+            self.add_exact(&filter);
+        }
+    }
+
+    pub(self) fn add_suffix(&mut self, domain: &str) {
+        self.filter_trie.insert_suffix(domain);
+    }
+
+    pub(self) fn add_exact(&mut self, domain: &str) {
+        self.filter_trie.insert_exact(domain);
+    }
+
+    // On-disk snapshot format (all integers little-endian):
+    //   magic "FDNS" | version u8
+    //   cursor u32 | cursor6 u128
+    //   v4_count u32 | (ip u32, domain_len u16, domain bytes){v4_count}
+    //   v6_count u32 | (ip6 u128, domain_len u16, domain bytes){v6_count}
+    //   lru_count u32 | (domain_len u16, domain bytes){lru_count}, oldest first
+    const STORE_MAGIC: &'static [u8; 4] = b"FDNS";
+    const STORE_VERSION: u8 = 1;
+
+    fn flush_store(&self) -> Result<()> {
+        let path = match &self.store_path {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(Self::STORE_MAGIC);
+        buf.push(Self::STORE_VERSION);
+        buf.extend_from_slice(&self.cursor.to_le_bytes());
+        buf.extend_from_slice(&self.cursor6.to_le_bytes());
+
+        buf.extend_from_slice(&(self.ip_to_domain.len() as u32).to_le_bytes());
+        for (ip, domain) in &self.ip_to_domain {
+            buf.extend_from_slice(&ip.to_le_bytes());
+            Self::write_domain(&mut buf, domain);
+        }
+
+        buf.extend_from_slice(&(self.ipv6_to_domain.len() as u32).to_le_bytes());
+        for (ip6, domain) in &self.ipv6_to_domain {
+            buf.extend_from_slice(&ip6.to_le_bytes());
+            Self::write_domain(&mut buf, domain);
+        }
+
+        buf.extend_from_slice(&(self.lru_order.len() as u32).to_le_bytes());
+        for domain in &self.lru_order {
+            Self::write_domain(&mut buf, domain);
+        }
+
+        let tmp_path = path.with_extension("tmp");
+        let mut f = fs::File::create(&tmp_path)?;
+        f.write_all(&buf)?;
+        fs::rename(&tmp_path, path)?;
+        info!("[FakeDNS] 已将映射表写入磁盘: {}", path.display());
+        Ok(())
+    }
+
+    fn load_store(&mut self) -> Result<()> {
+        let path = match &self.store_path {
+            Some(p) => p.clone(),
+            None => return Ok(()),
+        };
+        if !path.exists() {
+            return Ok(());
+        }
+
+        let mut f = fs::File::open(&path)?;
+        let mut buf = Vec::new();
+        f.read_to_end(&mut buf)?;
+
+        let mut r = &buf[..];
+        if r.len() < 5 || &r[..4] != Self::STORE_MAGIC {
+            return Err(anyhow!("invalid fake-dns store header"));
+        }
+        r = &r[4..];
+        let version = r[0];
+        r = &r[1..];
+        if version != Self::STORE_VERSION {
+            return Err(anyhow!("unsupported fake-dns store version {}", version));
+        }
+
+        self.cursor = Self::read_u32(&mut r)?;
+        self.cursor6 = Self::read_u128(&mut r)?;
+
+        let v4_count = Self::read_u32(&mut r)?;
+        for _ in 0..v4_count {
+            let ip = Self::read_u32(&mut r)?;
+            let domain = Self::read_domain(&mut r)?;
+            self.ip_to_domain.insert(ip, domain.clone());
+            self.domain_to_ip.insert(domain, ip);
+        }
+
+        let v6_count = Self::read_u32(&mut r)?;
+        for _ in 0..v6_count {
+            let ip6 = Self::read_u128(&mut r)?;
+            let domain = Self::read_domain(&mut r)?;
+            self.ipv6_to_domain.insert(ip6, domain.clone());
+            self.domain_to_ipv6.insert(domain, ip6);
+        }
+
+        let lru_count = Self::read_u32(&mut r)?;
+        for _ in 0..lru_count {
+            self.lru_order.push_back(Self::read_domain(&mut r)?);
+        }
+
+        info!(
+            "[FakeDNS] 从磁盘恢复映射表 | IPv4条目: {} | IPv6条目: {} | 路径: {}",
+            v4_count, v6_count, path.display()
+        );
+        Ok(())
+    }
+
+    fn write_domain(buf: &mut Vec<u8>, domain: &str) {
+        let bytes = domain.as_bytes();
+        buf.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
+        buf.extend_from_slice(bytes);
+    }
+
+    fn read_u32(r: &mut &[u8]) -> Result<u32> {
+        if r.len() < 4 {
+            return Err(anyhow!("truncated fake-dns store"));
+        }
+        let (head, tail) = r.split_at(4);
+        *r = tail;
+        Ok(u32::from_le_bytes(head.try_into().unwrap()))
+    }
+
+    fn read_u128(r: &mut &[u8]) -> Result<u128> {
+        if r.len() < 16 {
+            return Err(anyhow!("truncated fake-dns store"));
+        }
+        let (head, tail) = r.split_at(16);
+        *r = tail;
+        Ok(u128::from_le_bytes(head.try_into().unwrap()))
+    }
+
+    fn read_domain(r: &mut &[u8]) -> Result<String> {
+        if r.len() < 2 {
+            return Err(anyhow!("truncated fake-dns store"));
+        }
+        let (head, tail) = r.split_at(2);
+        *r = tail;
+        let len = u16::from_le_bytes(head.try_into().unwrap()) as usize;
+        if r.len() < len {
+            return Err(anyhow!("truncated fake-dns store"));
+        }
+        let (head, tail) = r.split_at(len);
+        *r = tail;
+        String::from_utf8(head.to_vec()).map_err(|e| anyhow!("invalid domain in store: {}", e))
     }
 
     pub(self) fn query_domain(&self, ip: &IpAddr) -> Option<String> {
-        let ip = match ip {
-            IpAddr::V4(ip) => ip,
-            _ => {
-                info!("[FakeDNS] 查询域名失败: 不支持的IP类型 {:?}", ip);
-                return None;
-            }
+        let result = match ip {
+            IpAddr::V4(ip) => self.ip_to_domain.get(&Self::ip_to_u32(ip)).cloned(),
+            IpAddr::V6(ip) => self
+                .ipv6_to_domain
+                .get(&Self::ipv6_to_u128(ip))
+                .cloned(),
         };
-        let result = self.ip_to_domain.get(&Self::ip_to_u32(ip)).cloned();
         info!("[FakeDNS] 查询域名 | IP: {} | 结果: {:?}", ip, result);
         result
     }
 
-    pub(self) fn query_fake_ip(&self, domain: &str) -> Option<IpAddr> {
+    pub(self) fn query_fake_ip(&mut self, domain: &str) -> Option<IpAddr> {
         let result = self.domain_to_ip
             .get(domain)
             .map(|v| IpAddr::V4(Self::u32_to_ip(v.to_owned())));
+        if result.is_some() {
+            self.touch_lru(domain);
+        }
         info!("[FakeDNS] 查询假IP | 域名: {} | 结果: {:?}", domain, result);
         result
     }
 
-    pub(self) fn generate_fake_response(&mut self, request: &[u8]) -> Result<Vec<u8>> {
+    // Move `domain` to the most-recently-used end of `lru_order`.
+    fn touch_lru(&mut self, domain: &str) {
+        if let Some(pos) = self.lru_order.iter().position(|d| d == domain) {
+            self.lru_order.remove(pos);
+        }
+        self.lru_order.push_back(domain.to_owned());
+    }
+
+    pub(self) fn query_fake_ipv6(&self, domain: &str) -> Option<IpAddr> {
+        let result = self.domain_to_ipv6
+            .get(domain)
+            .map(|v| IpAddr::V6(Self::u128_to_ipv6(v.to_owned())));
+        info!("[FakeDNS] 查询假IPv6 | 域名: {} | 结果: {:?}", domain, result);
+        result
+    }
+
+    pub(self) fn generate_fake_response(&mut self, request: &[u8]) -> Result<FakeDnsOutcome> {
         let req = match Message::from_vec(request) {
             Ok(req) => req,
             Err(e) => {
@@ -138,22 +419,48 @@ impl FakeDnsImpl {
         info!("[FakeDNS] 处理域名: {}", domain);
 
         if !self.accept(&domain) {
-            error!("[FakeDNS] 域名未被接受: {}", domain);
-            return Err(anyhow!("domain {} not accepted", domain));
+            info!("[FakeDNS] 域名未被接受，转发至上游解析器: {}", domain);
+            return Ok(FakeDnsOutcome::Forward);
         }
 
-        let ip = if let Some(ip) = self.query_fake_ip(&domain) {
-            match ip {
-                IpAddr::V4(a) => a,
-                _ => {
-                    error!("[FakeDNS] 意外的IPv6假IP");
-                    return Err(anyhow!("unexpected Ipv6 fake IP"));
+        // HTTPS/SVCB answers carry both ipv4hint and ipv6hint, so allocate
+        // both address families whenever the query needs either.
+        let ip = if t == RecordType::A || t == RecordType::HTTPS {
+            let ip = if let Some(ip) = self.query_fake_ip(&domain) {
+                match ip {
+                    IpAddr::V4(a) => a,
+                    _ => {
+                        error!("[FakeDNS] 意外的IPv6假IP");
+                        return Err(anyhow!("unexpected Ipv6 fake IP"));
+                    }
                 }
-            }
+            } else {
+                let ip = self.allocate_ip(&domain)?;
+                info!("[FakeDNS] 为域名分配新IP | 域名: {} | IP: {}", domain, ip);
+                ip
+            };
+            Some(ip)
+        } else {
+            None
+        };
+
+        let ip6 = if t == RecordType::AAAA || t == RecordType::HTTPS {
+            let ip6 = if let Some(ip6) = self.query_fake_ipv6(&domain) {
+                match ip6 {
+                    IpAddr::V6(a) => a,
+                    _ => {
+                        error!("[FakeDNS] 意外的IPv4假IP");
+                        return Err(anyhow!("unexpected Ipv4 fake IP"));
+                    }
+                }
+            } else {
+                let ip6 = self.allocate_ipv6(&domain)?;
+                info!("[FakeDNS] 为域名分配新IPv6 | 域名: {} | IP: {}", domain, ip6);
+                ip6
+            };
+            Some(ip6)
         } else {
-            let ip = self.allocate_ip(&domain)?;
-            info!("[FakeDNS] 为域名分配新IP | 域名: {} | IP: {}", domain, ip);
-            ip
+            None
         };
 
         let mut resp = Message::new();
@@ -170,77 +477,169 @@ impl FakeDnsImpl {
             resp.add_query(query.clone());
         }
 
-        if query.query_type() == RecordType::A {
-            let mut ans = Record::new();
-            ans.set_name(raw_name.clone())
-                .set_rr_type(RecordType::A)
-                .set_ttl(self.ttl)
-                .set_dns_class(DNSClass::IN)
-                .set_data(Some(RData::A(rdata::A(ip))));
-            resp.add_answer(ans);
-            info!("[FakeDNS] 生成DNS应答 | 域名: {} | IP: {} | TTL: {}", domain, ip, self.ttl);
+        if t == RecordType::A {
+            if let Some(ip) = ip {
+                let mut ans = Record::new();
+                ans.set_name(raw_name.clone())
+                    .set_rr_type(RecordType::A)
+                    .set_ttl(self.ttl)
+                    .set_dns_class(DNSClass::IN)
+                    .set_data(Some(RData::A(rdata::A(ip))));
+                resp.add_answer(ans);
+                info!("[FakeDNS] 生成DNS应答 | 域名: {} | IP: {} | TTL: {}", domain, ip, self.ttl);
+            }
+        }
+
+        if t == RecordType::AAAA {
+            if let Some(ip6) = ip6 {
+                let mut ans = Record::new();
+                ans.set_name(raw_name.clone())
+                    .set_rr_type(RecordType::AAAA)
+                    .set_ttl(self.ttl)
+                    .set_dns_class(DNSClass::IN)
+                    .set_data(Some(RData::AAAA(rdata::AAAA(ip6))));
+                resp.add_answer(ans);
+                info!("[FakeDNS] 生成DNS应答 | 域名: {} | IPv6: {} | TTL: {}", domain, ip6, self.ttl);
+            }
+        }
+
+        if t == RecordType::HTTPS {
+            if let (Some(ip), Some(ip6)) = (ip, ip6) {
+                let svc_params = vec![
+                    (
+                        SvcParamKey::Ipv4Hint,
+                        SvcParamValue::Ipv4Hint(IpHint(vec![rdata::A(ip)])),
+                    ),
+                    (
+                        SvcParamKey::Ipv6Hint,
+                        SvcParamValue::Ipv6Hint(IpHint(vec![rdata::AAAA(ip6)])),
+                    ),
+                ];
+                // priority 1 (ServiceMode) with target "." means the service
+                // endpoint is the record's owner name, i.e. the query name.
+                let svcb = SVCB::new(1, Name::root(), svc_params);
+
+                let mut ans = Record::new();
+                ans.set_name(raw_name.clone())
+                    .set_rr_type(RecordType::HTTPS)
+                    .set_ttl(self.ttl)
+                    .set_dns_class(DNSClass::IN)
+                    .set_data(Some(RData::HTTPS(rdata::HTTPS(svcb))));
+                resp.add_answer(ans);
+                info!(
+                    "[FakeDNS] 生成HTTPS应答 | 域名: {} | IP: {} | IPv6: {} | TTL: {}",
+                    domain, ip, ip6, self.ttl
+                );
+            }
         }
 
-        Ok(resp.to_vec()?)
+        Ok(FakeDnsOutcome::Answer(resp.to_vec()?))
     }
 
     pub(self) fn is_fake_ip(&self, ip: &IpAddr) -> bool {
-        let ip = match ip {
-            IpAddr::V4(ip) => ip,
-            _ => return false,
-        };
-        let ip = Self::ip_to_u32(ip);
-        ip >= self.min_cursor && ip <= self.max_cursor
+        match ip {
+            IpAddr::V4(ip) => {
+                let ip = Self::ip_to_u32(ip);
+                ip >= self.min_cursor && ip <= self.max_cursor
+            }
+            IpAddr::V6(ip) => {
+                let ip = Self::ipv6_to_u128(ip);
+                ip >= self.min_cursor6 && ip <= self.max_cursor6
+            }
+        }
     }
 
     fn allocate_ip(&mut self, domain: &str) -> Result<Ipv4Addr> {
-        if let Some(prev_domain) = self.ip_to_domain.insert(self.cursor, domain.to_owned()) {
-            info!("[FakeDNS] IP重用 | 旧域名: {} | 新域名: {} | IP: {}", 
-                prev_domain, domain, Self::u32_to_ip(self.cursor));
-            self.domain_to_ip.remove(&prev_domain);
-        }
-        self.domain_to_ip.insert(domain.to_owned(), self.cursor);
-        let ip = Self::u32_to_ip(self.cursor);
-        self.prepare_next_cursor()?;
+        // While the pool still has free slots, keep advancing the cursor.
+        // Only once the whole 198.18/16 space is occupied do we evict the
+        // least-recently-used domain instead of clobbering an active one.
+        let cursor = if self.domain_to_ip.len() < self.capacity {
+            self.find_free_cursor()?
+        } else {
+            let evicted = self
+                .lru_order
+                .pop_front()
+                .ok_or_else(|| anyhow!("fake IP pool full but LRU list is empty"))?;
+            let freed_cursor = self
+                .domain_to_ip
+                .remove(&evicted)
+                .ok_or_else(|| anyhow!("LRU domain {} has no IP mapping", evicted))?;
+            self.ip_to_domain.remove(&freed_cursor);
+            info!(
+                "[FakeDNS] LRU淘汰 | 旧域名: {} | 新域名: {} | IP: {}",
+                evicted, domain, Self::u32_to_ip(freed_cursor)
+            );
+            freed_cursor
+        };
+
+        self.ip_to_domain.insert(cursor, domain.to_owned());
+        self.domain_to_ip.insert(domain.to_owned(), cursor);
+        self.lru_order.push_back(domain.to_owned());
+        let ip = Self::u32_to_ip(cursor);
         info!("[FakeDNS] 分配IP | 域名: {} | IP: {}", domain, ip);
         Ok(ip)
     }
 
-    // Make sure `self.cursor` is valid and can be used immediately for next fake IP.
-    fn prepare_next_cursor(&mut self) -> Result<()> {
-        for _ in 0..3 {
+    // Advance `self.cursor` until it lands on an address that is neither a
+    // network/broadcast address (.0/.255) nor already occupied, and return it.
+    fn find_free_cursor(&mut self) -> Result<u32> {
+        let span = self.max_cursor - self.min_cursor + 1;
+        for _ in 0..span {
+            let candidate = self.cursor;
+            let octet4 = Self::u32_to_ip(candidate).octets()[3];
+
             self.cursor += 1;
             if self.cursor > self.max_cursor {
                 self.cursor = self.min_cursor;
             }
-            // avoid network and broadcast addresses
-            match Self::u32_to_ip(self.cursor).octets()[3] {
-                0 | 255 => {
-                    continue;
-                }
-                _ => return Ok(()),
+
+            if octet4 != 0 && octet4 != 255 && !self.ip_to_domain.contains_key(&candidate) {
+                return Ok(candidate);
             }
         }
-        Err(anyhow!("unable to prepare next cursor"))
+        Err(anyhow!("no free fake IP slot available"))
+    }
+
+    // Unlike `allocate_ip`'s v4 pool, this cursor has no LRU eviction: the
+    // v6 pool is 110 bits wide, so `cursor6` wrapping back onto a still-live
+    // domain (the same stale-mapping collision `lru_order` exists to avoid
+    // for v4) is not a practically reachable condition here.
+    fn allocate_ipv6(&mut self, domain: &str) -> Result<Ipv6Addr> {
+        if let Some(prev_domain) = self.ipv6_to_domain.insert(self.cursor6, domain.to_owned()) {
+            info!("[FakeDNS] IPv6重用 | 旧域名: {} | 新域名: {} | IP: {}",
+                prev_domain, domain, Self::u128_to_ipv6(self.cursor6));
+            self.domain_to_ipv6.remove(&prev_domain);
+        }
+        self.domain_to_ipv6.insert(domain.to_owned(), self.cursor6);
+        let ip6 = Self::u128_to_ipv6(self.cursor6);
+        self.prepare_next_cursor6()?;
+        info!("[FakeDNS] 分配IPv6 | 域名: {} | IP: {}", domain, ip6);
+        Ok(ip6)
+    }
+
+    // Make sure `self.cursor6` is valid and can be used immediately for next fake IPv6.
+    fn prepare_next_cursor6(&mut self) -> Result<()> {
+        self.cursor6 += 1;
+        if self.cursor6 > self.max_cursor6 {
+            self.cursor6 = self.min_cursor6;
+        }
+        Ok(())
     }
 
     fn accept(&self, domain: &str) -> bool {
+        let matched = self.filter_wildcard || self.filter_trie.matches(domain);
         let result = match self.mode {
             FakeDnsMode::Exclude => {
-                for d in &self.filters {
-                    if domain.contains(d) || d == "*" {
-                        info!("[FakeDNS] 域名被排除 | 域名: {} | 匹配规则: {}", domain, d);
-                        return false;
-                    }
+                if matched {
+                    info!("[FakeDNS] 域名被排除 | 域名: {}", domain);
+                    return false;
                 }
                 true
             }
             FakeDnsMode::Include => {
-                for d in &self.filters {
-                    if domain.contains(d) || d == "*" {
-                        info!("[FakeDNS] 域名被包含 | 域名: {} | 匹配规则: {}", domain, d);
-                        return true;
-                    }
+                if matched {
+                    info!("[FakeDNS] 域名被包含 | 域名: {}", domain);
+                    return true;
                 }
                 false
             }
@@ -256,6 +655,21 @@ impl FakeDnsImpl {
     fn ip_to_u32(ip: &Ipv4Addr) -> u32 {
         u32::from_be_bytes(ip.octets())
     }
+
+    fn ipv6_base() -> Ipv6Addr {
+        let mut octets = [0u8; 16];
+        octets[0] = FAKE_IPV6_PREFIX[0];
+        octets[1] = FAKE_IPV6_PREFIX[1];
+        Ipv6Addr::from(octets)
+    }
+
+    fn u128_to_ipv6(ip: u128) -> Ipv6Addr {
+        Ipv6Addr::from(ip)
+    }
+
+    fn ipv6_to_u128(ip: &Ipv6Addr) -> u128 {
+        u128::from_be_bytes(ip.octets())
+    }
 }
 
 #[cfg(test)]
@@ -277,4 +691,113 @@ mod tests {
         let ip2 = 2130706433u32;
         assert_eq!(ip1, ip2);
     }
+
+    #[test]
+    fn test_ipv6_roundtrip() {
+        let ip = FakeDnsImpl::ipv6_base();
+        let v = FakeDnsImpl::ipv6_to_u128(&ip);
+        assert_eq!(FakeDnsImpl::u128_to_ipv6(v), ip);
+    }
+
+    #[test]
+    fn test_is_fake_ip_v6_range() {
+        let dns = FakeDnsImpl::new(FakeDnsMode::Include);
+        let base = FakeDnsImpl::ipv6_base();
+        assert!(dns.is_fake_ip(&IpAddr::V6(base)));
+        assert!(!dns.is_fake_ip(&IpAddr::V6(Ipv6Addr::new(
+            0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8888
+        ))));
+    }
+
+    #[test]
+    fn test_lru_eviction_spares_recently_used_domain() {
+        let mut dns = FakeDnsImpl::new(FakeDnsMode::Include);
+        // Shrink the pool down to 2 usable slots so eviction is reachable
+        // without allocating tens of thousands of domains.
+        dns.max_cursor = dns.min_cursor + 255;
+        dns.capacity = 2;
+
+        dns.allocate_ip("a.com").unwrap();
+        dns.allocate_ip("b.com").unwrap();
+        // touch "a.com" so "b.com" becomes the least-recently-used entry
+        assert!(dns.query_fake_ip("a.com").is_some());
+
+        dns.allocate_ip("c.com").unwrap();
+
+        assert!(dns.query_fake_ip("a.com").is_some());
+        assert!(dns.query_fake_ip("b.com").is_none());
+        assert!(dns.query_fake_ip("c.com").is_some());
+    }
+
+    #[test]
+    fn test_accept_exclude_mode() {
+        let mut dns = FakeDnsImpl::new(FakeDnsMode::Exclude);
+        dns.add_suffix("ads.com");
+        dns.add_exact("full.example.com");
+
+        // Excluded (suffix/exact filter hit) domains are not accepted...
+        assert!(!dns.accept("www.ads.com"));
+        assert!(!dns.accept("ads.com"));
+        assert!(!dns.accept("full.example.com"));
+        // ...but everything else, including a near-miss on the exact rule, is.
+        assert!(dns.accept("sub.full.example.com"));
+        assert!(dns.accept("example.com"));
+    }
+
+    #[test]
+    fn test_accept_include_mode() {
+        let mut dns = FakeDnsImpl::new(FakeDnsMode::Include);
+        dns.add_suffix("example.com");
+
+        assert!(dns.accept("www.example.com"));
+        assert!(dns.accept("example.com"));
+        assert!(!dns.accept("other.com"));
+    }
+
+    #[test]
+    fn test_accept_wildcard_filter() {
+        let mut dns_include = FakeDnsImpl::new(FakeDnsMode::Include);
+        dns_include.add_filter("*".to_string());
+        assert!(dns_include.accept("anything.com"));
+
+        let mut dns_exclude = FakeDnsImpl::new(FakeDnsMode::Exclude);
+        dns_exclude.add_filter("*".to_string());
+        assert!(!dns_exclude.accept("anything.com"));
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let path = std::env::temp_dir().join(format!(
+            "leaf_fake_dns_store_test_{}.bin",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let mut dns = FakeDnsImpl::new(FakeDnsMode::Include);
+        dns.store_path = Some(path.clone());
+        dns.allocate_ip("a.com").unwrap();
+        dns.allocate_ip("b.com").unwrap();
+        dns.allocate_ipv6("a.com").unwrap();
+        dns.flush_store().unwrap();
+
+        let mut restored = FakeDnsImpl::new(FakeDnsMode::Include);
+        restored.store_path = Some(path.clone());
+        restored.load_store().unwrap();
+
+        assert_eq!(restored.cursor, dns.cursor);
+        assert_eq!(restored.cursor6, dns.cursor6);
+        let a_ip = FakeDnsImpl::u32_to_ip(dns.domain_to_ip["a.com"]);
+        assert_eq!(restored.query_domain(&IpAddr::V4(a_ip)), Some("a.com".to_string()));
+        assert_eq!(
+            restored.domain_to_ip.get("b.com"),
+            dns.domain_to_ip.get("b.com")
+        );
+        assert_eq!(
+            restored.domain_to_ipv6.get("a.com"),
+            dns.domain_to_ipv6.get("a.com")
+        );
+        assert_eq!(restored.lru_order, dns.lru_order);
+
+        fs::remove_file(&path).unwrap();
+    }
 }
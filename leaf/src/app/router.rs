@@ -1,19 +1,24 @@
-use std::collections::{HashMap};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::sync::RwLock;
+use std::time::{Duration, Instant};
 
 use anyhow::anyhow;
 use anyhow::Result;
 use cidr::IpCidr;
 use futures::TryFutureExt;
-use maxminddb::geoip2::Country;
+use maxminddb::geoip2::{Asn, Country};
 use maxminddb::Mmap;
 use tracing::{debug, info, warn};
 
+use crate::app::fake_dns::FakeDns;
+use crate::app::trie::TrieNode;
 use crate::app::SyncDnsClient;
 use crate::config;
 use crate::session::{Network, Session, SocksAddr};
 use crate::config::domain_rule::SMART_MATCHER;
+use crate::config::ip_rule::IP_RULE;
 
 pub trait Condition: Send + Sync + Unpin {
     fn apply(&self, sess: &Session) -> bool;
@@ -74,6 +79,106 @@ impl Condition for MmdbMatcher {
     }
 }
 
+struct AsnMatcher {
+    reader: Arc<maxminddb::Reader<Mmap>>,
+    asn: u32,
+}
+
+impl AsnMatcher {
+    fn new(reader: Arc<maxminddb::Reader<Mmap>>, asn: u32) -> Self {
+        debug!("Creating ASN matcher for AS{}", asn);
+        AsnMatcher { reader, asn }
+    }
+}
+
+impl Condition for AsnMatcher {
+    fn apply(&self, sess: &Session) -> bool {
+        if !sess.destination.is_domain() {
+            if let Some(ip) = sess.destination.ip() {
+                if let Ok(asn) = self.reader.lookup::<Asn>(ip) {
+                    if let Some(number) = asn.autonomous_system_number {
+                        if number == self.asn {
+                            debug!("[{}] matches asn [AS{}]", ip, self.asn);
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Matches a `Session`'s hostname against a mix of DOMAIN (suffix), FULL
+/// (exact), DOMAIN-KEYWORD and DOMAIN-REGEX rules in one condition, reusing
+/// `TrieNode` for the suffix/exact modes since it already distinguishes
+/// those two (see `app::trie`).
+struct DomainMatcher {
+    trie: TrieNode,
+    keywords: Vec<String>,
+    regex_set: Option<regex::RegexSet>,
+}
+
+impl DomainMatcher {
+    fn new(domains: &[config::router::rule::Domain]) -> Self {
+        let mut trie = TrieNode::new();
+        let mut keywords = Vec::new();
+        let mut patterns = Vec::new();
+
+        for d in domains {
+            match d.type_.enum_value() {
+                Ok(config::router::rule::domain::Type::DOMAIN) => trie.insert_suffix(&d.value),
+                Ok(config::router::rule::domain::Type::FULL) => trie.insert_exact(&d.value),
+                Ok(config::router::rule::domain::Type::PLAIN) => keywords.push(d.value.to_lowercase()),
+                Ok(config::router::rule::domain::Type::REGEX) => patterns.push(d.value.clone()),
+                _ => debug!("skipping domain rule with unknown type: {}", d.value),
+            }
+        }
+
+        let regex_set = if patterns.is_empty() {
+            None
+        } else {
+            match regex::RegexSet::new(&patterns) {
+                Ok(set) => Some(set),
+                Err(e) => {
+                    warn!("failed to compile domain regex set {:?}: {}", patterns, e);
+                    None
+                }
+            }
+        };
+
+        Self { trie, keywords, regex_set }
+    }
+}
+
+impl Condition for DomainMatcher {
+    fn apply(&self, sess: &Session) -> bool {
+        let Some(domain) = sess.destination.domain() else {
+            return false;
+        };
+
+        if self.trie.matches(domain) {
+            debug!("[{}] matches domain/domain-suffix rule", domain);
+            return true;
+        }
+
+        let lower = domain.to_lowercase();
+        if self.keywords.iter().any(|k| lower.contains(k.as_str())) {
+            debug!("[{}] matches domain-keyword rule", domain);
+            return true;
+        }
+
+        if let Some(set) = &self.regex_set {
+            if set.is_match(domain) {
+                debug!("[{}] matches domain-regex rule", domain);
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
 struct IpCidrMatcher {
     values: Vec<IpCidr>,
 }
@@ -308,16 +413,166 @@ impl Condition for SmartMatcher {
                 debug!("[{}] matches smart rule", domain);
                 return true;
             }
+            return false;
+        }
+
+        // 目的地是裸 IP（没有域名可判断），退回到 qqwry 库判断是否为中国大陆 IP
+        if let Some(std::net::IpAddr::V4(ip)) = sess.destination.ip() {
+            if IP_RULE.is_cn_ip(ip) {
+                debug!("[{}] matches smart rule via CN IP range", ip);
+                return true;
+            }
         }
         false
     }
 }
 
+/// Controls which resolved address family `pick_route`'s `domain_resolve`
+/// fallback prefers when re-matching rules against a domain's A/AAAA
+/// answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookupIpStrategy {
+    Ipv4Only,
+    Ipv6Only,
+    Ipv4ThenIpv6,
+    Ipv6ThenIpv4,
+}
+
+impl Default for LookupIpStrategy {
+    fn default() -> Self {
+        LookupIpStrategy::Ipv4ThenIpv6
+    }
+}
+
+impl LookupIpStrategy {
+    fn from_proto(v: config::router::LookupIpStrategy) -> Self {
+        match v {
+            config::router::LookupIpStrategy::IPV4_ONLY => LookupIpStrategy::Ipv4Only,
+            config::router::LookupIpStrategy::IPV6_ONLY => LookupIpStrategy::Ipv6Only,
+            config::router::LookupIpStrategy::IPV4_THEN_IPV6 => LookupIpStrategy::Ipv4ThenIpv6,
+            config::router::LookupIpStrategy::IPV6_THEN_IPV4 => LookupIpStrategy::Ipv6ThenIpv4,
+        }
+    }
+
+    /// Filters out the non-preferred family (for the `*Only` variants) and
+    /// stably reorders the rest so the preferred family is tried first,
+    /// keeping each family's own resolver-returned order intact.
+    fn apply(&self, ips: Vec<std::net::IpAddr>) -> Vec<std::net::IpAddr> {
+        match self {
+            LookupIpStrategy::Ipv4Only => ips.into_iter().filter(|ip| ip.is_ipv4()).collect(),
+            LookupIpStrategy::Ipv6Only => ips.into_iter().filter(|ip| ip.is_ipv6()).collect(),
+            LookupIpStrategy::Ipv4ThenIpv6 => {
+                let mut ips = ips;
+                ips.sort_by_key(|ip| !ip.is_ipv4());
+                ips
+            }
+            LookupIpStrategy::Ipv6ThenIpv4 => {
+                let mut ips = ips;
+                ips.sort_by_key(|ip| !ip.is_ipv6());
+                ips
+            }
+        }
+    }
+
+}
+
+/// Capacity of the router's `RouteCache`, and the TTL of every entry
+/// inserted without one of its own. `pick_route` inserts all of its
+/// entries, `domain_resolve` fallback matches included, without a TTL, so
+/// they all expire after this default rather than after a DNS record TTL.
+const ROUTE_CACHE_CAPACITY: usize = 10_000;
+const DEFAULT_ROUTE_CACHE_TTL: Duration = Duration::from_secs(300);
+
+struct RouteCacheEntry {
+    target: String,
+    expires_at: Instant,
+    /// Tick of the last access, used for approximate-LRU eviction. An
+    /// `AtomicU64` so a cache *hit* only needs to bump this field rather
+    /// than splice a shared recency list, letting lookups take a shared
+    /// read lock at the call site instead of an exclusive one.
+    last_used: AtomicU64,
+}
+
+/// Bounded LRU cache of routing decisions. Mirrors the DnsLru approach of
+/// pairing a cached value with its own expiry so stale decisions age out
+/// instead of pinning forever across DNS changes or config reloads.
+///
+/// Recency is tracked with a monotonic counter per entry (CLOCK-style)
+/// rather than a shared LRU list: a lookup just stamps the entry it found,
+/// so it never needs to mutate the map itself and can run under a shared
+/// read lock. Eviction, which does need to scan for the least-recently-used
+/// entry, only happens on insert and is already behind a write lock there.
+struct RouteCache {
+    capacity: usize,
+    default_ttl: Duration,
+    entries: HashMap<String, RouteCacheEntry>,
+    clock: AtomicU64,
+}
+
+impl RouteCache {
+    fn new(capacity: usize, default_ttl: Duration) -> Self {
+        Self {
+            capacity,
+            default_ttl,
+            entries: HashMap::new(),
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the cached target for `key`, treating an expired entry as a
+    /// miss, and bumps its recency tick on a hit. Doesn't need `&mut self`:
+    /// expiry is read-only and recency lives in an `AtomicU64`.
+    fn get(&self, key: &str) -> Option<String> {
+        let entry = self.entries.get(key)?;
+        if entry.expires_at <= Instant::now() {
+            return None;
+        }
+
+        entry
+            .last_used
+            .store(self.clock.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+        Some(entry.target.clone())
+    }
+
+    /// Inserts/refreshes `key` -> `target`, expiring after `ttl` (or this
+    /// cache's default TTL), evicting the least-recently-used entry first
+    /// if the cache is already at capacity.
+    fn insert(&mut self, key: String, target: String, ttl: Option<Duration>) {
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            if let Some(lru_key) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+                .map(|(key, _)| key.clone())
+            {
+                self.entries.remove(&lru_key);
+            }
+        }
+
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        let expires_at = Instant::now() + ttl.unwrap_or(self.default_ttl);
+        self.entries.insert(
+            key,
+            RouteCacheEntry {
+                target,
+                expires_at,
+                last_used: AtomicU64::new(tick),
+            },
+        );
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
 pub struct Router {
     rules: Vec<Rule>,
     domain_resolve: bool,
+    lookup_ip_strategy: LookupIpStrategy,
     dns_client: SyncDnsClient,
-    route_cache: RwLock<HashMap<String, String>>,
+    fake_dns: Option<Arc<FakeDns>>,
+    route_cache: RwLock<RouteCache>,
 }
 
 impl Router {
@@ -342,6 +597,10 @@ impl Router {
 
             let mut cond_and = ConditionAnd::new();
 
+            if !rule.domains.is_empty() {
+                cond_and.add(Box::new(DomainMatcher::new(&rule.domains)));
+            }
+
             if !rule.ip_cidrs.is_empty() {
                 cond_and.add(Box::new(IpCidrMatcher::new(&mut rule.ip_cidrs)));
             }
@@ -370,6 +629,27 @@ impl Router {
                 }
             }
 
+            if !rule.asns.is_empty() {
+                for asn_rule in rule.asns.iter() {
+                    let reader = match mmdb_readers.get(&asn_rule.file) {
+                        Some(r) => r.clone(),
+                        None => match maxminddb::Reader::open_mmap(&asn_rule.file) {
+                            Ok(r) => {
+                                info!("Successfully loaded mmdb file: {}", asn_rule.file);
+                                let r = Arc::new(r);
+                                mmdb_readers.insert(asn_rule.file.to_owned(), r.clone());
+                                r
+                            }
+                            Err(e) => {
+                                warn!("Failed to open mmdb file {}: {:?}", asn_rule.file, e);
+                                continue;
+                            }
+                        },
+                    };
+                    cond_and.add(Box::new(AsnMatcher::new(reader, asn_rule.asn)));
+                }
+            }
+
             if !rule.port_ranges.is_empty() {
                 cond_and.add(Box::new(PortMatcher::new(&rule.port_ranges)));
             }
@@ -392,19 +672,36 @@ impl Router {
     pub fn new(
         router: &mut protobuf::MessageField<config::Router>,
         dns_client: SyncDnsClient,
+    ) -> Self {
+        Self::new_with_fake_dns(router, dns_client, None)
+    }
+
+    /// Like [`Router::new`], additionally wiring in the [`FakeDns`] store so
+    /// bare fake-ip destinations can be resolved back to their original
+    /// hostname before routing (see [`Router::pick_route`]).
+    pub fn new_with_fake_dns(
+        router: &mut protobuf::MessageField<config::Router>,
+        dns_client: SyncDnsClient,
+        fake_dns: Option<Arc<FakeDns>>,
     ) -> Self {
         let mut rules: Vec<Rule> = Vec::new();
         let mut domain_resolve = false;
+        let mut lookup_ip_strategy = LookupIpStrategy::default();
         if let Some(router) = router.as_mut() {
             Self::load_rules(&mut rules, &mut router.rules);
             domain_resolve = router.domain_resolve;
+            if let Ok(strategy) = router.lookup_ip_strategy.enum_value() {
+                lookup_ip_strategy = LookupIpStrategy::from_proto(strategy);
+            }
         }
-        
+
         Router {
             rules,
             domain_resolve,
+            lookup_ip_strategy,
             dns_client,
-            route_cache: RwLock::new(HashMap::new()),
+            fake_dns,
+            route_cache: RwLock::new(RouteCache::new(ROUTE_CACHE_CAPACITY, DEFAULT_ROUTE_CACHE_TTL)),
         }
     }
 
@@ -413,35 +710,59 @@ impl Router {
         if let Some(router) = router.as_mut() {
             Self::load_rules(&mut self.rules, &mut router.rules);
             self.domain_resolve = router.domain_resolve;
+            if let Ok(strategy) = router.lookup_ip_strategy.enum_value() {
+                self.lookup_ip_strategy = LookupIpStrategy::from_proto(strategy);
+            }
         }
+        // Stale decisions may no longer hold after a config/DNS change, so
+        // don't let any of them survive the reload.
+        self.route_cache.write().unwrap().clear();
         Ok(())
     }
 
     pub async fn pick_route<'a>(&'a self, sess: &'a Session) -> Result<String> {
-        let cache_key = if sess.destination.is_domain() {
-            sess.destination.domain()
+        // Bare IP destinations that happen to be fake-ips we handed out
+        // ourselves carry no usable hostname for domain-based rules. Ask
+        // the FakeDns store for the original domain and re-point the
+        // session at it before doing anything else, so rule matching and
+        // the route cache key both operate on the real name.
+        let mut recovered_sess = None;
+        if !sess.destination.is_domain() {
+            if let (Some(fake_dns), Some(ip)) = (&self.fake_dns, sess.destination.ip()) {
+                if let Some(domain) = fake_dns.query_domain(&ip).await {
+                    debug!("🔁 recovered fake-ip [{}] to domain [{}]", ip, domain);
+                    let mut new_sess = sess.clone();
+                    new_sess.destination = SocksAddr::Domain(domain, sess.destination.port());
+                    recovered_sess = Some(new_sess);
+                }
+            }
+        }
+        let matched_sess = recovered_sess.as_ref().unwrap_or(sess);
+
+        let cache_key = if matched_sess.destination.is_domain() {
+            matched_sess.destination.domain()
                 .ok_or_else(|| anyhow!("illegal domain name"))?
                 .to_string()
-        } else if let Some(ip) = sess.destination.ip() {
+        } else if let Some(ip) = matched_sess.destination.ip() {
             ip.to_string()
         } else {
             // Return "Direct" tag for invalid destination addresses
             return Ok("Direct".to_string());
         };
-        
+
         if let Some(target) = self.route_cache.read().unwrap().get(&cache_key) {
-            info!("🦜 route cache hit for {} -> {}", &sess.destination, target);
-            return Ok(target.clone());
+            info!("🦜 route cache hit for {} -> {}", &matched_sess.destination, target);
+            return Ok(target);
         }
 
-        info!("🦑 picking route for {}:{}", &sess.network, &sess.destination);
+        info!("🦑 picking route for {}:{}", &matched_sess.network, &matched_sess.destination);
 
         for rule in &self.rules {
             let start = std::time::Instant::now();
-            let matched = rule.apply(sess);
+            let matched = rule.apply(matched_sess);
             let elapsed = start.elapsed();
-            
-            if let Some(domain) = sess.destination.domain() {
+
+            if let Some(domain) = matched_sess.destination.domain() {
                 debug!(
                     "routing domain [{}] on rule [{}] took {:?}, matched: {}",
                     domain,
@@ -449,73 +770,86 @@ impl Router {
                     elapsed,
                     matched
                 );
-            } else if let Some(ip) = sess.destination.ip() {
+            } else if let Some(ip) = matched_sess.destination.ip() {
                 debug!(
                     "routing ip [{}] on rule [{}] took {:?}, matched: {}",
                     ip,
-                    rule.target, 
+                    rule.target,
                     elapsed,
                     matched
                 );
             }
 
             if matched {
-                info!("🎯 matched rule [{}] for [{}]", 
-                    rule.target, 
-                    sess.destination
+                info!("🎯 matched rule [{}] for [{}]",
+                    rule.target,
+                    matched_sess.destination
                 );
 
                 let target = rule.target.clone();
-                self.route_cache.write().unwrap().insert(
-                    cache_key,
-                    target.clone()
-                );
+                self.route_cache.write().unwrap().insert(cache_key, target.clone(), None);
                 return Ok(target);
             }
         }
 
-        if sess.destination.is_domain() && self.domain_resolve {
+        if matched_sess.destination.is_domain() && self.domain_resolve {
             let ips = {
                 self.dns_client
                     .read()
                     .await
                     .lookup(
-                        sess.destination
+                        matched_sess.destination
                             .domain()
                             .ok_or_else(|| anyhow!("illegal domain name"))?,
                     )
-                    .map_err(|e| anyhow!("lookup {} failed: {}", sess.destination.host(), e))
+                    .map_err(|e| anyhow!("lookup {} failed: {}", matched_sess.destination.host(), e))
                     .await?
             };
-            if !ips.is_empty() {
-                let mut new_sess = sess.clone();
-                new_sess.destination = SocksAddr::from((ips[0], sess.destination.port()));
+            let ips = self.lookup_ip_strategy.apply(ips);
+            for ip in ips {
+                let mut new_sess = matched_sess.clone();
+                new_sess.destination = SocksAddr::from((ip, matched_sess.destination.port()));
                 debug!(
                     "re-matching with resolved ip [{}] for [{}]",
-                    ips[0],
-                    sess.destination.host()
+                    ip,
+                    matched_sess.destination.host()
                 );
                 for rule in &self.rules {
                     if rule.apply(&new_sess) {
                         info!("🎯 matched rule [{}] for resolved IP [{}]", rule.target, new_sess.destination);
                         let target = rule.target.clone();
-                        self.route_cache.write().unwrap().insert(
-                            cache_key,
-                            target.clone()
-                        );
+                        // `dns_client.lookup` doesn't currently surface the
+                        // resolved record's TTL, so this falls back to the
+                        // cache's default TTL like a direct rule match.
+                        self.route_cache.write().unwrap().insert(cache_key, target.clone(), None);
                         return Ok(target);
                     }
                 }
             }
         }
 
+        // A recovered fake-ip only rewrote the session into a domain for
+        // rule matching above; it never stops the *original* IP destination
+        // from being routed. If neither the recovered domain nor (when
+        // enabled) its resolved IPs matched anything, fall back to matching
+        // the original IP destination, so an IP-CIDR rule (or any rule with
+        // no domain condition) still gets a chance, same as it would if
+        // FakeDns recovery hadn't happened at all.
+        if recovered_sess.is_some() {
+            for rule in &self.rules {
+                if rule.apply(sess) {
+                    info!("🎯 matched rule [{}] for original destination [{}]", rule.target, sess.destination);
+                    let target = rule.target.clone();
+                    self.route_cache.write().unwrap().insert(cache_key, target.clone(), None);
+                    return Ok(target);
+                }
+            }
+        }
+
         // When no rules match, default to "trojan_out" tag
         let default_target = "trojan_out".to_string();
-        info!("⚡ no rules matched, using default route [{}] for [{}]", default_target, sess.destination);
-        self.route_cache.write().unwrap().insert(
-            cache_key,
-            default_target.clone()
-        );
+        info!("⚡ no rules matched, using default route [{}] for [{}]", default_target, matched_sess.destination);
+        self.route_cache.write().unwrap().insert(cache_key, default_target.clone(), None);
         Ok(default_target)
     }
 }
@@ -526,6 +860,91 @@ mod tests {
 
     use super::*;
 
+    fn domain_rule(type_: config::router::rule::domain::Type, value: &str) -> config::router::rule::Domain {
+        let mut d = config::router::rule::Domain::default();
+        d.type_ = protobuf::EnumOrUnknown::new(type_);
+        d.value = value.to_string();
+        d
+    }
+
+    #[test]
+    fn test_domain_matcher() {
+        use config::router::rule::domain::Type;
+
+        let matcher = DomainMatcher::new(&[
+            domain_rule(Type::DOMAIN, "example.net"),
+            domain_rule(Type::FULL, "full.example.com"),
+            domain_rule(Type::PLAIN, "ads"),
+            domain_rule(Type::REGEX, "^api\\d+\\.example\\.org$"),
+        ]);
+
+        let mut sess = Session {
+            destination: SocksAddr::Domain("www.example.net".to_string(), 443),
+            ..Default::default()
+        };
+        assert!(matcher.apply(&sess));
+
+        sess.destination = SocksAddr::Domain("full.example.com".to_string(), 443);
+        assert!(matcher.apply(&sess));
+        sess.destination = SocksAddr::Domain("sub.full.example.com".to_string(), 443);
+        assert!(!matcher.apply(&sess));
+
+        sess.destination = SocksAddr::Domain("cdn.ads.net".to_string(), 443);
+        assert!(matcher.apply(&sess));
+
+        sess.destination = SocksAddr::Domain("api42.example.org".to_string(), 443);
+        assert!(matcher.apply(&sess));
+        sess.destination = SocksAddr::Domain("api42.example.com".to_string(), 443);
+        assert!(!matcher.apply(&sess));
+
+        sess.destination = SocksAddr::Domain("unrelated.org".to_string(), 443);
+        assert!(!matcher.apply(&sess));
+    }
+
+    #[test]
+    fn test_lookup_ip_strategy_apply() {
+        let v4: std::net::IpAddr = "1.1.1.1".parse().unwrap();
+        let v4b: std::net::IpAddr = "2.2.2.2".parse().unwrap();
+        let v6: std::net::IpAddr = "::1".parse().unwrap();
+        let v6b: std::net::IpAddr = "::2".parse().unwrap();
+        let ips = vec![v4, v6, v4b, v6b];
+
+        assert_eq!(LookupIpStrategy::Ipv4Only.apply(ips.clone()), vec![v4, v4b]);
+        assert_eq!(LookupIpStrategy::Ipv6Only.apply(ips.clone()), vec![v6, v6b]);
+        assert_eq!(
+            LookupIpStrategy::Ipv4ThenIpv6.apply(ips.clone()),
+            vec![v4, v4b, v6, v6b]
+        );
+        assert_eq!(
+            LookupIpStrategy::Ipv6ThenIpv4.apply(ips),
+            vec![v6, v6b, v4, v4b]
+        );
+    }
+
+    #[test]
+    fn test_route_cache_expires_entries() {
+        let mut cache = RouteCache::new(10, Duration::from_secs(60));
+        cache.insert("example.com".to_string(), "proxy".to_string(), Some(Duration::from_millis(10)));
+        assert_eq!(cache.get("example.com"), Some("proxy".to_string()));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(cache.get("example.com"), None);
+    }
+
+    #[test]
+    fn test_route_cache_evicts_least_recently_used() {
+        let mut cache = RouteCache::new(2, Duration::from_secs(60));
+        cache.insert("a.com".to_string(), "a".to_string(), None);
+        cache.insert("b.com".to_string(), "b".to_string(), None);
+        // Touch "a.com" so "b.com" becomes the least-recently-used entry.
+        assert_eq!(cache.get("a.com"), Some("a".to_string()));
+
+        cache.insert("c.com".to_string(), "c".to_string(), None);
+        assert_eq!(cache.get("b.com"), None);
+        assert_eq!(cache.get("a.com"), Some("a".to_string()));
+        assert_eq!(cache.get("c.com"), Some("c".to_string()));
+    }
+
     #[test]
     fn test_port_matcher() {
         let mut sess = Session {
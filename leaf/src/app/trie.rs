@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 use tracing::debug;
 
-// Trie 树节点结构
+// Trie 树节点结构，按反转的域名标签组织（从顶级域名向内）
 pub struct TrieNode {
-    // 标记当前节点是否是一个域名后缀的结尾
-    is_end: bool,
+    // 标记当前节点是否是一个域名后缀规则的结尾（匹配自身及其所有子域名）
+    is_suffix: bool,
+    // 标记当前节点是否是一个精确匹配规则的结尾（只匹配自身）
+    is_exact: bool,
     // 子节点映射表,key 是域名部分,value 是子节点
     children: HashMap<String, TrieNode>,
 }
@@ -13,46 +15,64 @@ impl TrieNode {
     pub fn new() -> Self {
         debug!("创建新的 Trie 节点");
         Self {
-            is_end: false,
+            is_suffix: false,
+            is_exact: false,
             children: HashMap::new(),
         }
     }
 
-    // 插入一个域名后缀到 Trie 树
-    pub fn insert(&mut self, domain: &str) {
-        debug!("开始向 Trie 树插入域名后缀: {}", domain);
+    fn walk_to_leaf(&mut self, domain: &str) -> &mut TrieNode {
         let parts: Vec<&str> = domain.split('.').rev().collect();
         debug!("域名分割后的部分(反转): {:?}", parts);
-        
+
         let mut current = self;
         for (i, part) in parts.iter().enumerate() {
             debug!("处理第 {} 个部分: {}", i + 1, part);
-            current = current.children
+            current = current
+                .children
                 .entry(part.to_string())
                 .or_insert_with(|| {
                     debug!("创建新的子节点: {}", part);
                     TrieNode::new()
                 });
         }
-        current.is_end = true;
+        current
+    }
+
+    // 插入一个域名后缀规则：匹配该域名及其所有子域名
+    pub fn insert_suffix(&mut self, domain: &str) {
+        debug!("开始向 Trie 树插入域名后缀: {}", domain);
+        self.walk_to_leaf(domain).is_suffix = true;
         debug!("域名后缀 {} 插入完成", domain);
     }
 
-    // 检查一个域名是否匹配任何已存储的后缀
+    // 插入一个精确匹配规则：只匹配这个域名本身
+    pub fn insert_exact(&mut self, domain: &str) {
+        debug!("开始向 Trie 树插入精确域名: {}", domain);
+        self.walk_to_leaf(domain).is_exact = true;
+        debug!("精确域名 {} 插入完成", domain);
+    }
+
+    // 保留旧接口：等价于插入一个域名后缀规则
+    pub fn insert(&mut self, domain: &str) {
+        self.insert_suffix(domain);
+    }
+
+    // 检查一个域名是否匹配任何已存储的后缀规则或精确规则
     pub fn matches(&self, domain: &str) -> bool {
         debug!("开始匹配域名: {}", domain);
         let parts: Vec<&str> = domain.split('.').rev().collect();
         debug!("域名分割后的部分(反转): {:?}", parts);
-        
+
         let mut current = self;
         for (i, part) in parts.iter().enumerate() {
             debug!("检查第 {} 个部分: {}", i + 1, part);
-            
-            if current.is_end {
-                debug!("在检查 {} 时发现匹配的后缀", part);
+
+            if current.is_suffix {
+                debug!("在检查 {} 时发现匹配的后缀规则", part);
                 return true;
             }
-            
+
             match current.children.get(*part) {
                 Some(node) => {
                     debug!("找到子节点: {}", part);
@@ -64,8 +84,8 @@ impl TrieNode {
                 }
             }
         }
-        
-        let matched = current.is_end;
+
+        let matched = current.is_suffix || current.is_exact;
         if matched {
             debug!("完全匹配成功");
         } else {
@@ -73,4 +93,43 @@ impl TrieNode {
         }
         matched
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suffix_matches_self_and_subdomains() {
+        let mut trie = TrieNode::new();
+        trie.insert_suffix("example.com");
+
+        assert!(trie.matches("example.com"));
+        assert!(trie.matches("www.example.com"));
+        assert!(trie.matches("a.b.example.com"));
+        assert!(!trie.matches("notexample.com"));
+        assert!(!trie.matches("example.org"));
+    }
+
+    #[test]
+    fn test_exact_matches_only_itself() {
+        let mut trie = TrieNode::new();
+        trie.insert_exact("example.com");
+
+        assert!(trie.matches("example.com"));
+        assert!(!trie.matches("www.example.com"));
+        assert!(!trie.matches("notexample.com"));
+    }
+
+    #[test]
+    fn test_suffix_and_exact_rules_coexist() {
+        let mut trie = TrieNode::new();
+        trie.insert_exact("full.example.com");
+        trie.insert_suffix("example.com");
+
+        assert!(trie.matches("full.example.com"));
+        assert!(trie.matches("other.example.com"));
+        assert!(trie.matches("example.com"));
+        assert!(!trie.matches("full.example.org"));
+    }
+}
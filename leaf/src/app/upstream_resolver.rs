@@ -0,0 +1,458 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
+use tracing::{debug, error, info};
+use trust_dns_proto::op::{
+    header::MessageType, op_code::OpCode, response_code::ResponseCode, Message,
+};
+use trust_dns_proto::rr::{dns_class::DNSClass, record_type::RecordType, resource::Record};
+use trust_dns_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use trust_dns_resolver::TokioAsyncResolver;
+
+use crate::config::domain_rule::SMART_MATCHER;
+
+/// Resolves DNS queries that `FakeDns` declined to fake-answer, so domains
+/// rejected by `accept()` (Exclude-matched, or non-matching in Include mode)
+/// still get a genuine answer instead of an error.
+#[async_trait]
+pub trait UpstreamResolver: Send + Sync {
+    /// Takes a raw wire-format DNS query and returns a raw wire-format
+    /// response, mirroring `FakeDns::generate_fake_response`.
+    async fn resolve(&self, request: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// An [`UpstreamResolver`] backed by trust-dns, speaking DoH or DoT to a
+/// single upstream depending on how it was constructed.
+pub struct DohDotResolver {
+    resolver: TokioAsyncResolver,
+}
+
+impl DohDotResolver {
+    /// Builds a resolver that queries `url` (e.g. `https://1.1.1.1/dns-query`)
+    /// over DNS-over-HTTPS.
+    pub fn new_doh(url: &str) -> Result<Self> {
+        info!("[UpstreamResolver] 初始化 DoH 解析器: {}", url);
+        let config = ResolverConfig::from_parts(
+            None,
+            vec![],
+            NameServerConfigGroup::from_urls_https(vec![url.parse()?], vec![], true),
+        );
+        let resolver = TokioAsyncResolver::tokio(config, ResolverOpts::default());
+        Ok(Self { resolver })
+    }
+
+    /// Builds a resolver that queries `addr` (e.g. `1.1.1.1:853`) over
+    /// DNS-over-TLS, validating the server's certificate against `tls_name`.
+    pub fn new_dot(addr: std::net::SocketAddr, tls_name: String) -> Result<Self> {
+        info!("[UpstreamResolver] 初始化 DoT 解析器: {} ({})", addr, tls_name);
+        let config = ResolverConfig::from_parts(
+            None,
+            vec![],
+            NameServerConfigGroup::from_ips_tls(&[addr.ip()], addr.port(), tls_name, true),
+        );
+        let resolver = TokioAsyncResolver::tokio(config, ResolverOpts::default());
+        Ok(Self { resolver })
+    }
+}
+
+/// An [`UpstreamResolver`] backed by trust-dns, speaking plaintext UDP/TCP to
+/// one or more upstreams (e.g. `223.5.5.5`, `119.29.29.29`) — typically
+/// paired with [`RacingResolver`] as the "domestic" group in a [`SplitResolver`].
+pub struct PlainResolver {
+    resolver: TokioAsyncResolver,
+}
+
+impl PlainResolver {
+    /// Builds a resolver that queries `addrs` (e.g. `223.5.5.5:53`) over
+    /// plaintext DNS.
+    pub fn new(addrs: &[std::net::SocketAddr]) -> Result<Self> {
+        info!("[UpstreamResolver] 初始化明文解析器: {:?}", addrs);
+        let ips: Vec<std::net::IpAddr> = addrs.iter().map(|a| a.ip()).collect();
+        let port = addrs.first().map(|a| a.port()).unwrap_or(53);
+        let config = ResolverConfig::from_parts(
+            None,
+            vec![],
+            NameServerConfigGroup::from_ips_clear(&ips, port, true),
+        );
+        let resolver = TokioAsyncResolver::tokio(config, ResolverOpts::default());
+        Ok(Self { resolver })
+    }
+}
+
+#[async_trait]
+impl UpstreamResolver for PlainResolver {
+    async fn resolve(&self, request: &[u8]) -> Result<Vec<u8>> {
+        resolve_via(&self.resolver, request).await
+    }
+}
+
+#[async_trait]
+impl UpstreamResolver for DohDotResolver {
+    async fn resolve(&self, request: &[u8]) -> Result<Vec<u8>> {
+        resolve_via(&self.resolver, request).await
+    }
+}
+
+/// Shared resolve logic for any [`UpstreamResolver`] backed by a plain
+/// `TokioAsyncResolver`, regardless of which transport it was configured
+/// with (plaintext, DoH, or DoT).
+async fn resolve_via(resolver: &TokioAsyncResolver, request: &[u8]) -> Result<Vec<u8>> {
+    let req = Message::from_vec(request)
+        .map_err(|e| anyhow!("failed to parse upstream DNS request: {}", e))?;
+
+    let query = req
+        .queries()
+        .first()
+        .ok_or_else(|| anyhow!("no queries in this DNS request"))?;
+
+    let name = query.name().clone();
+    let record_type = query.query_type();
+
+    let lookup = resolver
+        .lookup(name.clone(), record_type)
+        .await
+        .map_err(|e| {
+            error!("[UpstreamResolver] 上游解析失败 | 域名: {} | 错误: {}", name, e);
+            anyhow!("upstream lookup for {} failed: {}", name, e)
+        })?;
+
+    let mut resp = Message::new();
+    resp.set_id(req.id())
+        .set_message_type(MessageType::Response)
+        .set_op_code(req.op_code())
+        .set_response_code(ResponseCode::NoError);
+    if resp.op_code() == OpCode::Query {
+        resp.set_recursion_desired(req.recursion_desired())
+            .set_checking_disabled(req.checking_disabled());
+    }
+    resp.add_query(query.clone());
+
+    for record in lookup.record_iter() {
+        let mut ans = Record::new();
+        ans.set_name(name.clone())
+            .set_rr_type(record.record_type())
+            .set_ttl(record.ttl())
+            .set_dns_class(DNSClass::IN)
+            .set_data(record.data().cloned());
+        resp.add_answer(ans);
+    }
+
+    info!(
+        "[UpstreamResolver] 转发解析完成 | 域名: {} | 类型: {:?} | 应答数: {}",
+        name,
+        record_type,
+        resp.answers().len()
+    );
+
+    Ok(resp.to_vec()?)
+}
+
+/// Races several [`UpstreamResolver`]s concurrently and returns whichever
+/// answers first without error, so one slow or unreachable upstream doesn't
+/// stall resolution. Mirrors the "并发自动选择最快的 DNS" behavior of
+/// racing a domestic and an encrypted resolver group.
+pub struct RacingResolver {
+    resolvers: Vec<Arc<dyn UpstreamResolver>>,
+}
+
+impl RacingResolver {
+    pub fn new(resolvers: Vec<Arc<dyn UpstreamResolver>>) -> Self {
+        Self { resolvers }
+    }
+}
+
+#[async_trait]
+impl UpstreamResolver for RacingResolver {
+    async fn resolve(&self, request: &[u8]) -> Result<Vec<u8>> {
+        if self.resolvers.is_empty() {
+            return Err(anyhow!("no upstream resolvers configured in this group"));
+        }
+
+        let mut pending: FuturesUnordered<_> = self
+            .resolvers
+            .iter()
+            .cloned()
+            .map(|resolver| {
+                let request = request.to_vec();
+                async move { resolver.resolve(&request).await }
+            })
+            .collect();
+
+        let mut last_err = None;
+        while let Some(result) = pending.next().await {
+            match result {
+                Ok(resp) => return Ok(resp),
+                Err(e) => {
+                    debug!("[UpstreamResolver] 候选解析器失败，等待其余候选: {}", e);
+                    last_err = Some(e);
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow!("all upstream resolvers in this group failed")))
+    }
+}
+
+/// Splits resolution between a "domestic" resolver group and an "encrypted"
+/// resolver group based on [`SMART_MATCHER`]'s CN classification of the
+/// query name, so CN domains resolve via fast plaintext upstreams while
+/// everything else resolves via DoH/DoT.
+pub struct SplitResolver {
+    domestic: Arc<dyn UpstreamResolver>,
+    encrypted: Arc<dyn UpstreamResolver>,
+}
+
+impl SplitResolver {
+    pub fn new(domestic: Arc<dyn UpstreamResolver>, encrypted: Arc<dyn UpstreamResolver>) -> Self {
+        Self { domestic, encrypted }
+    }
+}
+
+#[async_trait]
+impl UpstreamResolver for SplitResolver {
+    async fn resolve(&self, request: &[u8]) -> Result<Vec<u8>> {
+        let req = Message::from_vec(request)
+            .map_err(|e| anyhow!("failed to parse upstream DNS request: {}", e))?;
+        let query = req
+            .queries()
+            .first()
+            .ok_or_else(|| anyhow!("no queries in this DNS request"))?;
+
+        let domain = query.name().to_string();
+        let domain = domain.trim_end_matches('.');
+
+        if SMART_MATCHER.is_match(domain) {
+            debug!("[UpstreamResolver] {} 判定为国内域名，使用明文解析组", domain);
+            self.domestic.resolve(request).await
+        } else {
+            debug!("[UpstreamResolver] {} 判定为国外域名，使用加密解析组", domain);
+            self.encrypted.resolve(request).await
+        }
+    }
+}
+
+/// A cached response, valid until `expires_at`.
+struct CacheEntry {
+    response: Vec<u8>,
+    expires_at: Instant,
+}
+
+/// Wraps another [`UpstreamResolver`] with an in-memory response cache keyed
+/// by query name + record type, honoring the minimum TTL among the answer's
+/// records (defaulting to 60s for answers with no records, e.g. NXDOMAIN).
+pub struct CachingResolver {
+    inner: Arc<dyn UpstreamResolver>,
+    cache: RwLock<HashMap<(String, RecordType), CacheEntry>>,
+}
+
+impl CachingResolver {
+    pub fn new(inner: Arc<dyn UpstreamResolver>) -> Self {
+        Self {
+            inner,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl UpstreamResolver for CachingResolver {
+    async fn resolve(&self, request: &[u8]) -> Result<Vec<u8>> {
+        let req = Message::from_vec(request)
+            .map_err(|e| anyhow!("failed to parse upstream DNS request: {}", e))?;
+        let query = req
+            .queries()
+            .first()
+            .ok_or_else(|| anyhow!("no queries in this DNS request"))?;
+        let key = (query.name().to_string(), query.query_type());
+
+        if let Some(entry) = self.cache.read().unwrap().get(&key) {
+            if entry.expires_at > Instant::now() {
+                debug!("[UpstreamResolver] 缓存命中: {} {:?}", key.0, key.1);
+                let mut resp = Message::from_vec(&entry.response)
+                    .map_err(|e| anyhow!("failed to parse cached DNS response: {}", e))?;
+                resp.set_id(req.id());
+                return Ok(resp.to_vec()?);
+            }
+        }
+
+        let response = self.inner.resolve(request).await?;
+
+        let ttl = Message::from_vec(&response)
+            .ok()
+            .and_then(|resp| resp.answers().iter().map(|a| a.ttl()).min())
+            .unwrap_or(60);
+
+        self.cache.write().unwrap().insert(
+            key,
+            CacheEntry {
+                response: response.clone(),
+                expires_at: Instant::now() + Duration::from_secs(ttl as u64),
+            },
+        );
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use trust_dns_proto::rr::{rdata::A, Name, RData};
+
+    use super::*;
+
+    /// Builds a raw wire-format query for `name`, mirroring what `FakeDns`
+    /// hands to an `UpstreamResolver`.
+    fn query(id: u16, name: &str) -> Vec<u8> {
+        let mut msg = Message::new();
+        msg.set_id(id).set_message_type(MessageType::Query);
+        msg.add_query(trust_dns_proto::op::Query::query(
+            Name::from_str(name).unwrap(),
+            RecordType::A,
+        ));
+        msg.to_vec().unwrap()
+    }
+
+    /// Builds a raw wire-format response to `req` with a single A answer
+    /// valid for `ttl` seconds.
+    fn response(req: &[u8], ttl: u32) -> Vec<u8> {
+        let req = Message::from_vec(req).unwrap();
+        let query = req.queries().first().unwrap().clone();
+
+        let mut resp = Message::new();
+        resp.set_id(req.id()).set_message_type(MessageType::Response);
+        resp.add_query(query.clone());
+
+        let mut ans = Record::new();
+        ans.set_name(query.name().clone())
+            .set_rr_type(RecordType::A)
+            .set_ttl(ttl)
+            .set_dns_class(DNSClass::IN)
+            .set_data(Some(RData::A(A::new(1, 2, 3, 4))));
+        resp.add_answer(ans);
+
+        resp.to_vec().unwrap()
+    }
+
+    /// An [`UpstreamResolver`] stub that either errors, or waits `delay`
+    /// before returning a canned response, counting how many times it was
+    /// asked — used to prove `RacingResolver` picks the fastest non-error
+    /// candidate.
+    struct StubResolver {
+        delay: Duration,
+        fail: bool,
+        calls: AtomicUsize,
+    }
+
+    impl StubResolver {
+        fn ok(delay: Duration) -> Self {
+            Self { delay, fail: false, calls: AtomicUsize::new(0) }
+        }
+
+        fn err() -> Self {
+            Self { delay: Duration::from_millis(0), fail: true, calls: AtomicUsize::new(0) }
+        }
+    }
+
+    #[async_trait]
+    impl UpstreamResolver for StubResolver {
+        async fn resolve(&self, request: &[u8]) -> Result<Vec<u8>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.delay > Duration::from_millis(0) {
+                tokio::time::sleep(self.delay).await;
+            }
+            if self.fail {
+                return Err(anyhow!("stub resolver failed"));
+            }
+            Ok(response(request, 60))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_racing_resolver_returns_fastest_success() {
+        let fast = Arc::new(StubResolver::ok(Duration::from_millis(0)));
+        let slow = Arc::new(StubResolver::ok(Duration::from_millis(200)));
+        // The slow candidate is listed first so that it has been started
+        // before the fast one answers.
+        let racer = RacingResolver::new(vec![slow.clone(), fast.clone()]);
+
+        let req = query(1, "example.com.");
+        let start = Instant::now();
+        let resp = racer.resolve(&req).await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(200));
+        let resp = Message::from_vec(&resp).unwrap();
+        assert_eq!(resp.id(), 1);
+
+        assert_eq!(fast.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(slow.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_racing_resolver_skips_errors() {
+        let failing = Arc::new(StubResolver::err());
+        let ok = Arc::new(StubResolver::ok(Duration::from_millis(10)));
+        let racer = RacingResolver::new(vec![failing, ok]);
+
+        let req = query(2, "example.com.");
+        let resp = racer.resolve(&req).await.unwrap();
+        let resp = Message::from_vec(&resp).unwrap();
+        assert_eq!(resp.id(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_racing_resolver_fails_when_all_fail() {
+        let racer = RacingResolver::new(vec![Arc::new(StubResolver::err()), Arc::new(StubResolver::err())]);
+        let req = query(3, "example.com.");
+        assert!(racer.resolve(&req).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_caching_resolver_expires_and_rewrites_id() {
+        let inner = Arc::new(StubResolverWithTtl::new(1));
+        let cache = CachingResolver::new(inner.clone());
+
+        let first = cache.resolve(&query(10, "example.com.")).await.unwrap();
+        assert_eq!(Message::from_vec(&first).unwrap().id(), 10);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+
+        // Still within TTL: served from cache, with the new request's id,
+        // and without calling the inner resolver again.
+        let second = cache.resolve(&query(11, "example.com.")).await.unwrap();
+        assert_eq!(Message::from_vec(&second).unwrap().id(), 11);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        // Past TTL: falls through to the inner resolver again.
+        let third = cache.resolve(&query(12, "example.com.")).await.unwrap();
+        assert_eq!(Message::from_vec(&third).unwrap().id(), 12);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+    }
+
+    /// Like [`StubResolver`], but its canned response carries a caller-chosen
+    /// TTL so [`CachingResolver`] expiry can be exercised deterministically.
+    struct StubResolverWithTtl {
+        ttl: u32,
+        calls: AtomicUsize,
+    }
+
+    impl StubResolverWithTtl {
+        fn new(ttl: u32) -> Self {
+            Self { ttl, calls: AtomicUsize::new(0) }
+        }
+    }
+
+    #[async_trait]
+    impl UpstreamResolver for StubResolverWithTtl {
+        async fn resolve(&self, request: &[u8]) -> Result<Vec<u8>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(response(request, self.ttl))
+        }
+    }
+}
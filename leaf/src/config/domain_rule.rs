@@ -1,77 +1,56 @@
 use std::fs::File;
 use std::io::Read;
+use std::path::PathBuf;
 use lazy_static::lazy_static;
 use std::sync::Arc;
 use tracing::{info, error};
 
+use crate::config::public_suffix::PublicSuffixList;
+
 pub struct DomainRule {
     binary_domains: Vec<u128>,
 }
 
 impl DomainRule {
-    /// 获取域名的可注册部分
-    /// 
-    /// 规则:
-    /// 1. 处理特殊的中国相关顶级域名，如 .com.cn, .net.cn 等
-    /// 2. 处理常见的二级域名，如 .com, .net 等
-    /// 3. 如果不在已知列表中，保持原样返回
-    /// 
-    /// 示例:
-    /// - www.example.com.cn -> example.com.cn
-    /// - sub.example.com -> example.com
-    /// - example.cn -> example.cn
-    /// - www.example.co.uk -> example.co.uk (国外特殊域名也一并处理)
-    fn get_registrable_domain(domain: &str) -> String {
-        // 如果域名以 www. 开头，去掉它
-        let domain = if domain.starts_with("www.") {
-            &domain[4..]
+    pub(crate) fn exe_dir() -> std::io::Result<PathBuf> {
+        let exe_path = std::env::current_exe()?;
+        if cfg!(test) {
+            exe_path
+                .parent()
+                .ok_or_else(|| std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "Could not determine deps directory"
+                ))?
+                .parent()
+                .ok_or_else(|| std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "Could not determine debug directory"
+                ))
+                .map(|p| p.to_path_buf())
         } else {
-            domain
-        };
-
-        let parts: Vec<&str> = domain.split('.').collect();
-        if parts.len() < 2 {
-            return domain.to_string();
-        }
-
-        // 特殊的三级域名后缀
-        const SPECIAL_SUFFIXES: [&str; 14] = [
-            "com.cn", "net.cn", "org.cn", "gov.cn", 
-            "edu.cn", "mil.cn", "ac.cn", "ah.cn",
-            "bj.cn", "sh.cn", "tj.cn", "hz.cn",
-            "co.uk", "co.jp"  // 附加一些常见的国外特殊后缀
-        ];
-
-        // 常见的二级域名后缀
-        const COMMON_SUFFIXES: [&str; 12] = [
-            "cn", "com", "net", "org", "edu",
-            "gov", "mil", "biz", "info", "pro",
-            "name", "xyz"
-        ];
-        
-        // 1. 检查是否是特殊的三级域名
-        if parts.len() >= 3 {
-            let possible_special = parts[parts.len()-2..].join(".");
-            if SPECIAL_SUFFIXES.contains(&possible_special.as_str()) {
-                return if parts.len() == 3 {
-                    domain.to_string()
-                } else {
-                    format!("{}.{}", parts[parts.len()-3], possible_special)
-                };
-            }
-        }
-
-        // 2. 检查是否是普通的二级域名
-        if COMMON_SUFFIXES.contains(&parts.last().unwrap()) {
-            return if parts.len() == 2 {
-                domain.to_string()
-            } else {
-                format!("{}.{}", parts[parts.len()-2], parts.last().unwrap())
-            };
+            exe_path
+                .parent()
+                .ok_or_else(|| std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "Could not determine executable directory"
+                ))
+                .map(|p| p.to_path_buf())
         }
+    }
 
-        // 3. 如果不在已知列表中，返回原始域名
-        domain.to_string()
+    /// 获取域名的可注册部分（eTLD+1）
+    ///
+    /// 委托给 [`PUBLIC_SUFFIX_LIST`]：先按 IDNA 规则将每个标签转为 Punycode
+    /// （`xn--` 形式）并转为小写，再在 Public Suffix List 规则树中查找最长
+    /// 匹配的有效顶级域（exception 优先于 wildcard，wildcard 优先于普通规
+    /// 则），最终返回该有效顶级域再加左边一级标签。
+    ///
+    /// 示例:
+    /// - www.example.com.cn -> example.com.cn
+    /// - sub.example.com -> example.com
+    /// - 例子.中国 -> xn--fsqu00a.xn--fiqs8s
+    pub(crate) fn get_registrable_domain(domain: &str) -> String {
+        PUBLIC_SUFFIX_LIST.registrable_domain(domain)
     }
 
     /// 将域名转换为用于二分查找的 u128 值
@@ -92,28 +71,7 @@ impl DomainRule {
     }
 
     pub fn new() -> std::io::Result<Self> {
-        let exe_path = std::env::current_exe()?;
-        let exe_dir = if cfg!(test) {
-            exe_path.parent()
-                .ok_or_else(|| std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "Could not determine deps directory"
-                ))?
-                .parent()
-                .ok_or_else(|| std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "Could not determine debug directory"
-                ))?
-                .to_path_buf()
-        } else {
-            exe_path.parent()
-                .ok_or_else(|| std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "Could not determine executable directory"
-                ))?
-                .to_path_buf()
-        };
-
+        let exe_dir = Self::exe_dir()?;
         let binary_path = exe_dir.join("site_cn_binary.dat");
 
         info!("📂 Attempting to load binary file: {}", binary_path.display());
@@ -258,6 +216,19 @@ lazy_static! {
             }
         }
     };
+
+    static ref PUBLIC_SUFFIX_LIST: PublicSuffixList = {
+        let load = || -> std::io::Result<PublicSuffixList> {
+            PublicSuffixList::load(&DomainRule::exe_dir()?.join("public_suffix.dat"))
+        };
+        match load() {
+            Ok(psl) => psl,
+            Err(e) => {
+                error!("❌ Failed to initialize PUBLIC_SUFFIX_LIST: {}", e);
+                panic!("Failed to initialize PUBLIC_SUFFIX_LIST: {}", e);
+            }
+        }
+    };
 }
 
 #[cfg(test)]
@@ -410,7 +381,7 @@ mod tests {
             
             // 未知后缀测试
             ("example.unknown", "example.unknown"),
-            ("sub.example.unknown", "sub.example.unknown"),
+            ("sub.example.unknown", "example.unknown"),
             ("t2.xiaohongshu.com", "xiaohongshu.com"),
             
             // 边界情况测试
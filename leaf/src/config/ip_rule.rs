@@ -0,0 +1,233 @@
+use std::fs::File;
+use std::io::Read;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use lazy_static::lazy_static;
+use std::sync::Arc;
+use tracing::{info, error};
+
+use crate::config::domain_rule::DomainRule;
+
+/// 一条 qqwry 索引表记录：起始 IP 及其记录体在文件中的偏移。
+struct IndexEntry {
+    start_ip: u32,
+    record_offset: u32,
+}
+
+/// 基于纯真 IP 库（qqwry.dat）的 IP 归属地匹配器。
+///
+/// 文件头 8 字节是两个小端 u32：索引表的起始偏移 `first_index` 和结束偏移
+/// `last_index`；索引表每条记录 7 字节（4 字节小端起始 IP + 3 字节小端记录
+/// 偏移），条目数为 `(last_index - first_index) / 7 + 1`。记录体开头 4 字节
+/// 是结束 IP，随后是两个 GBK 编码的字符串（国家 / 地区），每个字符串既可以
+/// 内联（以 NUL 结尾），也可以通过重定向字节跳转：模式 `0x01` 表示整条记录
+/// 被重定位到随后的 3 字节偏移处，模式 `0x02` 表示只有地区字符串被重定位。
+pub struct IpRule {
+    index: Vec<IndexEntry>,
+    data: Vec<u8>,
+}
+
+impl IpRule {
+    pub fn new() -> std::io::Result<Self> {
+        let exe_dir = DomainRule::exe_dir()?;
+        let qqwry_path = exe_dir.join("qqwry.dat");
+        Self::load(&qqwry_path)
+    }
+
+    fn load(path: &Path) -> std::io::Result<Self> {
+        info!("📂 Attempting to load qqwry file: {}", path.display());
+
+        let mut file = File::open(path)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+
+        if data.len() < 8 {
+            error!("❌ qqwry data too short to contain a header");
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "qqwry data too short to contain a header",
+            ));
+        }
+
+        let first_index = u32::from_le_bytes(data[0..4].try_into().unwrap());
+        let last_index = u32::from_le_bytes(data[4..8].try_into().unwrap());
+
+        if last_index < first_index {
+            error!("❌ qqwry index range is invalid: first={} last={}", first_index, last_index);
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "qqwry index range is invalid",
+            ));
+        }
+
+        let entry_count = (last_index - first_index) / 7 + 1;
+        let mut index = Vec::with_capacity(entry_count as usize);
+
+        for i in 0..entry_count {
+            let offset = (first_index + i * 7) as usize;
+            let entry = data.get(offset..offset + 7).ok_or_else(|| {
+                error!("❌ qqwry index entry {} is out of bounds", i);
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "qqwry index entry out of bounds")
+            })?;
+
+            let start_ip = u32::from_le_bytes(entry[0..4].try_into().unwrap());
+            let record_offset = u32::from_le_bytes([entry[4], entry[5], entry[6], 0]);
+
+            index.push(IndexEntry { start_ip, record_offset });
+        }
+
+        info!("✅ Loaded {} qqwry index entries", index.len());
+
+        Ok(Self { index, data })
+    }
+
+    /// 读取记录体开头的结束 IP，随后跟着国家 / 地区字符串（可能被重定向）。
+    fn end_ip_at(&self, record_offset: u32) -> Option<u32> {
+        let offset = record_offset as usize;
+        let bytes = self.data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// 判断给定 IPv4 地址是否落在某条中国大陆 IP 段内。
+    ///
+    /// 只需要判断中国归属，所以这里不解码 GBK 字符串，只用来定位落在哪条
+    /// 记录里，再检查该记录体紧随结束 IP 之后的国家字符串是否以 CZ88.NET
+    /// 的"中国"标记开头 —— qqwry 对中国大陆地址的记录固定以 GBK 编码的
+    /// "中国"（0xD6 0xD0 0xB9 0xFA）开头。
+    pub fn is_cn_ip(&self, ip: Ipv4Addr) -> bool {
+        let target = u32::from_be_bytes(ip.octets());
+
+        let idx = match self.index.binary_search_by(|e| e.start_ip.cmp(&target)) {
+            Ok(i) => i,
+            Err(0) => return false,
+            Err(i) => i - 1,
+        };
+
+        let entry = &self.index[idx];
+        let Some(end_ip) = self.end_ip_at(entry.record_offset) else {
+            return false;
+        };
+        if target > end_ip {
+            return false;
+        }
+
+        let country_offset = entry.record_offset as usize + 4;
+        self.country_string_is_china(country_offset)
+    }
+
+    /// 解析国家字符串起始位置（处理 0x01/0x02 重定向），判断它是否是 GBK
+    /// 编码的"中国"。
+    fn country_string_is_china(&self, offset: usize) -> bool {
+        const CHINA_GBK: [u8; 4] = [0xD6, 0xD0, 0xB9, 0xFA];
+
+        let Some(&mode) = self.data.get(offset) else {
+            return false;
+        };
+
+        let resolved_offset = match mode {
+            0x01 | 0x02 => {
+                let Some(bytes) = self.data.get(offset + 1..offset + 4) else {
+                    return false;
+                };
+                u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]) as usize
+            }
+            _ => offset,
+        };
+
+        self.data
+            .get(resolved_offset..resolved_offset + 4)
+            .map(|prefix| prefix == CHINA_GBK)
+            .unwrap_or(false)
+    }
+}
+
+lazy_static! {
+    pub static ref IP_RULE: Arc<IpRule> = {
+        match IpRule::new() {
+            Ok(rule) => Arc::new(rule),
+            Err(e) => {
+                error!("❌ Failed to initialize IP_RULE: {}", e);
+                panic!("Failed to initialize IP_RULE: {}", e);
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 手工拼出一个最小的 qqwry 文件：一个中国大陆记录段和一个非中国记录段，
+    // 都内联编码（不使用重定向），用来验证索引二分查找和 GBK 前缀判断逻辑。
+    fn build_fixture() -> Vec<u8> {
+        let mut data = Vec::new();
+
+        // 头部先占位，之后回填索引表的起止偏移
+        data.extend_from_slice(&[0u8; 8]);
+
+        // 记录体 1: 1.0.0.0 - 1.0.0.255，国家字符串 "中国"
+        let record1_offset = data.len() as u32;
+        data.extend_from_slice(&u32::to_le_bytes(Ipv4Addr::new(1, 0, 0, 255).into()));
+        data.extend_from_slice(&[0xD6, 0xD0, 0xB9, 0xFA, 0x00]); // "中国" + NUL
+        data.extend_from_slice(&[0x00]); // 地区字符串（内联，空）
+
+        // 记录体 2: 2.0.0.0 - 2.0.0.255，国家字符串非中国
+        let record2_offset = data.len() as u32;
+        data.extend_from_slice(&u32::to_le_bytes(Ipv4Addr::new(2, 0, 0, 255).into()));
+        data.extend_from_slice(&[0x4A, 0x50, 0x00]); // 随便的非中国前缀 + NUL
+        data.extend_from_slice(&[0x00]);
+
+        // 索引表：每条 7 字节（4 字节起始 IP + 3 字节偏移）
+        let index_offset = data.len() as u32;
+        let ip1_start: u32 = Ipv4Addr::new(1, 0, 0, 0).into();
+        let ip2_start: u32 = Ipv4Addr::new(2, 0, 0, 0).into();
+
+        data.extend_from_slice(&u32::to_le_bytes(ip1_start));
+        data.extend_from_slice(&record1_offset.to_le_bytes()[0..3]);
+
+        data.extend_from_slice(&u32::to_le_bytes(ip2_start));
+        data.extend_from_slice(&record2_offset.to_le_bytes()[0..3]);
+
+        let last_entry_offset = index_offset + 7;
+        data[0..4].copy_from_slice(&index_offset.to_le_bytes());
+        data[4..8].copy_from_slice(&last_entry_offset.to_le_bytes());
+
+        data
+    }
+
+    fn fixture_rule() -> IpRule {
+        let data = build_fixture();
+        let first_index = u32::from_le_bytes(data[0..4].try_into().unwrap());
+        let last_index = u32::from_le_bytes(data[4..8].try_into().unwrap());
+        let entry_count = (last_index - first_index) / 7 + 1;
+
+        let mut index = Vec::with_capacity(entry_count as usize);
+        for i in 0..entry_count {
+            let offset = (first_index + i * 7) as usize;
+            let entry = &data[offset..offset + 7];
+            let start_ip = u32::from_le_bytes(entry[0..4].try_into().unwrap());
+            let record_offset = u32::from_le_bytes([entry[4], entry[5], entry[6], 0]);
+            index.push(IndexEntry { start_ip, record_offset });
+        }
+
+        IpRule { index, data }
+    }
+
+    #[test]
+    fn test_is_cn_ip_matches_china_range() {
+        let rule = fixture_rule();
+        assert!(rule.is_cn_ip(Ipv4Addr::new(1, 0, 0, 42)));
+    }
+
+    #[test]
+    fn test_is_cn_ip_rejects_other_range() {
+        let rule = fixture_rule();
+        assert!(!rule.is_cn_ip(Ipv4Addr::new(2, 0, 0, 42)));
+    }
+
+    #[test]
+    fn test_is_cn_ip_rejects_unlisted_ip() {
+        let rule = fixture_rule();
+        assert!(!rule.is_cn_ip(Ipv4Addr::new(8, 8, 8, 8)));
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use tracing::{error, info};
+
+/// A node in the reverse-label suffix tree built from a Public Suffix List.
+///
+/// Rules come in three flavors: a normal rule (`com.cn`) marks `is_rule` on
+/// the node reached by walking its labels from the TLD inward; a wildcard
+/// rule (`*.ck`) marks `has_wildcard` on the node one level up, meaning any
+/// single label under it is itself a suffix; an exception rule (`!www.ck`)
+/// marks `is_exception` on the node for the excepted label, which removes
+/// that one label from the wildcard suffix it would otherwise match.
+#[derive(Default)]
+struct PslNode {
+    children: HashMap<String, PslNode>,
+    is_rule: bool,
+    is_exception: bool,
+    has_wildcard: bool,
+}
+
+impl PslNode {
+    fn child_mut(&mut self, label: &str) -> &mut PslNode {
+        self.children.entry(label.to_string()).or_default()
+    }
+}
+
+/// Computes the registrable domain ("eTLD+1") for a hostname using a parsed
+/// ICANN/private Public Suffix List, per the algorithm at
+/// <https://publicsuffix.org/list/>.
+pub struct PublicSuffixList {
+    root: PslNode,
+}
+
+impl PublicSuffixList {
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        info!("📂 Loading public suffix list: {}", path.display());
+        let mut f = File::open(path)?;
+        let mut data = String::new();
+        f.read_to_string(&mut data)?;
+        Ok(Self::parse(&data))
+    }
+
+    pub fn parse(data: &str) -> Self {
+        let mut root = PslNode::default();
+        for line in data.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            Self::insert_rule(&mut root, line);
+        }
+        Self { root }
+    }
+
+    fn insert_rule(root: &mut PslNode, rule: &str) {
+        let (is_exception, rule) = match rule.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, rule),
+        };
+        let Some(normalized) = Self::normalize(rule) else {
+            error!("⚠️ skipping malformed PSL rule: {}", rule);
+            return;
+        };
+
+        if let Some(rest) = normalized.strip_prefix("*.") {
+            let labels: Vec<&str> = rest.split('.').rev().collect();
+            let mut node = &mut *root;
+            for label in &labels {
+                node = node.child_mut(label);
+            }
+            node.has_wildcard = true;
+        } else {
+            let labels: Vec<&str> = normalized.split('.').rev().collect();
+            let mut node = &mut *root;
+            for label in &labels {
+                node = node.child_mut(label);
+            }
+            if is_exception {
+                node.is_exception = true;
+            } else {
+                node.is_rule = true;
+            }
+        }
+    }
+
+    /// Lowercases and Punycode-encodes (per IDNA) every label of `domain`.
+    fn normalize(domain: &str) -> Option<String> {
+        idna::domain_to_ascii(domain).ok()
+    }
+
+    /// Returns the registrable domain ("eTLD+1") for `domain`.
+    pub fn registrable_domain(&self, domain: &str) -> String {
+        let Some(normalized) = Self::normalize(domain) else {
+            return domain.to_string();
+        };
+
+        let labels: Vec<&str> = normalized.split('.').rev().collect();
+        if labels.len() < 2 {
+            return normalized;
+        }
+
+        // Number of labels (counted from the TLD inward) that make up the
+        // effective TLD. Exceptions win over wildcards win over exact rules,
+        // and we always keep the longest match found while walking down.
+        let mut etld_len = 0usize;
+        let mut node = &self.root;
+        for (i, label) in labels.iter().enumerate() {
+            if node.has_wildcard {
+                let excepted = node
+                    .children
+                    .get(*label)
+                    .map(|n| n.is_exception)
+                    .unwrap_or(false);
+                etld_len = if excepted { i } else { i + 1 };
+            }
+            match node.children.get(*label) {
+                Some(child) => {
+                    node = child;
+                    if node.is_rule {
+                        etld_len = i + 1;
+                    }
+                }
+                None => break,
+            }
+        }
+
+        // No listed rule matched at all: per the PSL spec this falls back to
+        // the implicit "*" rule, i.e. the rightmost label is the eTLD.
+        if etld_len == 0 {
+            etld_len = 1;
+        }
+
+        if etld_len >= labels.len() {
+            // The whole name is itself a public suffix; there's nothing to
+            // register under it, so there's no narrower answer to give.
+            return normalized;
+        }
+
+        labels[..=etld_len]
+            .iter()
+            .rev()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn psl() -> PublicSuffixList {
+        PublicSuffixList::parse(
+            "com\nnet\norg\ncom.cn\nco.uk\nco.jp\n*.ck\n!www.ck\nxn--fiqs8s\n",
+        )
+    }
+
+    #[test]
+    fn test_normal_rule() {
+        let psl = psl();
+        assert_eq!(psl.registrable_domain("www.example.com"), "example.com");
+        assert_eq!(psl.registrable_domain("sub.example.com"), "example.com");
+    }
+
+    #[test]
+    fn test_multi_label_rule() {
+        let psl = psl();
+        assert_eq!(psl.registrable_domain("www.example.com.cn"), "example.com.cn");
+        assert_eq!(psl.registrable_domain("www.example.co.uk"), "example.co.uk");
+    }
+
+    #[test]
+    fn test_wildcard_rule() {
+        let psl = psl();
+        assert_eq!(psl.registrable_domain("foo.bar.ck"), "foo.bar.ck");
+    }
+
+    #[test]
+    fn test_exception_rule() {
+        let psl = psl();
+        assert_eq!(psl.registrable_domain("www.ck"), "www.ck");
+    }
+
+    #[test]
+    fn test_unknown_tld_falls_back_to_implicit_rule() {
+        let psl = psl();
+        assert_eq!(psl.registrable_domain("sub.example.unknown"), "example.unknown");
+    }
+
+    #[test]
+    fn test_idna_punycode_normalization() {
+        let psl = psl();
+        // "中国" (China) Punycode-encodes to xn--fiqs8s, which we listed as
+        // a suffix rule above; the Unicode label should resolve the same way.
+        let result = psl.registrable_domain("例子.中国");
+        assert!(result.is_ascii());
+        assert_eq!(result, idna::domain_to_ascii("例子.中国").unwrap());
+    }
+}
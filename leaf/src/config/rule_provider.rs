@@ -0,0 +1,364 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use aho_corasick::AhoCorasick;
+use tracing::{error, info, warn};
+
+use crate::app::trie::TrieNode;
+use crate::config::domain_rule::DomainRule;
+
+/// Which match mode a [`RuleProvider`] rule fired under, so callers can log
+/// or weight the decision differently per mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleMatchKind {
+    /// Matched the full hostname exactly (Clash `DOMAIN`).
+    Exact,
+    /// Matched the hostname or one of its parent domains (Clash `DOMAIN-SUFFIX`).
+    Suffix,
+    /// Matched via the binary registrable-domain list, which (like `Suffix`)
+    /// fires for the registrable domain itself and any of its subdomains,
+    /// not just a literal hit.
+    RegistrableSuffix,
+    /// The hostname contains one of the configured substrings (Clash `DOMAIN-KEYWORD`).
+    Keyword,
+}
+
+/// Converts a hostname as given (not reduced to its registrable domain)
+/// into a u128 for the text-rule exact table (Clash `DOMAIN`). Only the
+/// last 16 bytes of the lowercased name are kept, so two names longer than
+/// that which end in the same 16 bytes (such as a name and a long parent
+/// domain) get the same value and match each other.
+fn domain_to_u128(domain: &str) -> u128 {
+    let domain = domain.to_lowercase();
+    let mut bytes = [0u8; 16];
+    let domain_bytes = domain.as_bytes();
+    let len = domain_bytes.len().min(16);
+    bytes[..len].copy_from_slice(&domain_bytes[domain_bytes.len().saturating_sub(16)..]);
+    u128::from_le_bytes(bytes)
+}
+
+/// Converts a domain to its registrable-domain u128 encoding, using the
+/// exact same reduction and byte layout as `DomainRule::domain_to_u128`, so
+/// binary site lists built in that format (whose entries are already
+/// registrable domains, e.g. `baidu.com`) stay binary-search compatible
+/// when loaded here and queried with a subdomain like `www.baidu.com`.
+fn registrable_domain_to_u128(domain: &str) -> u128 {
+    domain_to_u128(&DomainRule::get_registrable_domain(domain))
+}
+
+struct RuleSet {
+    exact: Vec<u128>,
+    registrable: Vec<u128>,
+    suffix_trie: TrieNode,
+    keywords: Vec<String>,
+    keyword_set: Option<AhoCorasick>,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            exact: Vec::new(),
+            registrable: Vec::new(),
+            suffix_trie: TrieNode::new(),
+            keywords: Vec::new(),
+            keyword_set: None,
+        }
+    }
+}
+
+impl RuleSet {
+    fn is_match(&self, domain: &str) -> Option<RuleMatchKind> {
+        let value = domain_to_u128(domain);
+        if self.exact.binary_search(&value).is_ok() {
+            return Some(RuleMatchKind::Exact);
+        }
+
+        if !self.registrable.is_empty() {
+            let registrable_value = registrable_domain_to_u128(domain);
+            if self.registrable.binary_search(&registrable_value).is_ok() {
+                return Some(RuleMatchKind::RegistrableSuffix);
+            }
+        }
+
+        if self.suffix_trie.matches(domain) {
+            return Some(RuleMatchKind::Suffix);
+        }
+
+        if let Some(set) = &self.keyword_set {
+            if set.is_match(domain) {
+                return Some(RuleMatchKind::Keyword);
+            }
+        }
+
+        None
+    }
+}
+
+/// Loads domain rules from either the existing sorted-u128 binary format or
+/// Clash-style plain-text rule lists (`DOMAIN,`/`DOMAIN-SUFFIX,`/`DOMAIN-KEYWORD,`
+/// lines), builds a structure tailored to each match mode, and can be
+/// refreshed in place on an interval or on SIGHUP without restarting.
+pub struct RuleProvider {
+    rules: RwLock<RuleSet>,
+    binary_path: Option<PathBuf>,
+    text_path: Option<PathBuf>,
+}
+
+impl RuleProvider {
+    /// Creates an empty provider; call [`RuleProvider::reload`] (or one of
+    /// the `with_*` constructors) to populate it before use.
+    pub fn new() -> Self {
+        Self {
+            rules: RwLock::new(RuleSet::default()),
+            binary_path: None,
+            text_path: None,
+        }
+    }
+
+    /// Loads the legacy sorted-u128 binary list, as produced for
+    /// `site_cn_binary.dat`. Entries are registrable domains, so a query is
+    /// matched by its own registrable domain (matching subdomains), not the
+    /// literal hostname.
+    pub fn with_binary(mut self, path: PathBuf) -> std::io::Result<Self> {
+        self.binary_path = Some(path);
+        self.reload()?;
+        Ok(self)
+    }
+
+    /// Loads a Clash-style plain-text rule list in addition to (or instead
+    /// of) the binary list.
+    pub fn with_text(mut self, path: PathBuf) -> std::io::Result<Self> {
+        self.text_path = Some(path);
+        self.reload()?;
+        Ok(self)
+    }
+
+    fn load_binary(path: &Path) -> std::io::Result<Vec<u128>> {
+        info!("📂 Loading rule provider binary list: {}", path.display());
+        let mut file = File::open(path)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+
+        if data.len() % 16 != 0 {
+            error!("❌ rule provider binary length ({}) is not a multiple of 16", data.len());
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "rule provider binary length is not a multiple of 16",
+            ));
+        }
+
+        let mut values: Vec<u128> = data
+            .chunks_exact(16)
+            .map(|chunk| {
+                let mut bytes = [0u8; 16];
+                bytes.copy_from_slice(chunk);
+                u128::from_le_bytes(bytes)
+            })
+            .collect();
+        values.sort_unstable();
+        Ok(values)
+    }
+
+    /// Parses Clash-style lines of the form `DOMAIN,example.com`,
+    /// `DOMAIN-SUFFIX,example.com` or `DOMAIN-KEYWORD,ads`. Blank lines and
+    /// `#`-comments are skipped; unrecognized tags are logged and ignored.
+    fn load_text(path: &Path, exact: &mut Vec<u128>, suffix_trie: &mut TrieNode, keywords: &mut Vec<String>) -> std::io::Result<()> {
+        info!("📂 Loading rule provider text list: {}", path.display());
+        let mut file = File::open(path)?;
+        let mut data = String::new();
+        file.read_to_string(&mut data)?;
+
+        for line in data.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((tag, value)) = line.split_once(',') else {
+                warn!("⚠️ skipping malformed rule provider line: {}", line);
+                continue;
+            };
+            let value = value.trim();
+
+            match tag.trim() {
+                "DOMAIN" => exact.push(domain_to_u128(value)),
+                "DOMAIN-SUFFIX" => suffix_trie.insert_suffix(value),
+                "DOMAIN-KEYWORD" => keywords.push(value.to_lowercase()),
+                other => warn!("⚠️ unrecognized rule provider tag '{}' in line: {}", other, line),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rebuilds every match-mode structure from whichever source paths
+    /// this provider was constructed with, then swaps them in atomically.
+    ///
+    /// The binary list is kept separate from the text `DOMAIN` list: its
+    /// entries are registrable domains (built the same way as
+    /// `DomainRule`'s `site_cn_binary.dat`), so it's matched by reducing
+    /// the query to its registrable domain, whereas `DOMAIN` matches only
+    /// the literal hostname given.
+    pub fn reload(&self) -> std::io::Result<()> {
+        let mut registrable = match &self.binary_path {
+            Some(path) => Self::load_binary(path)?,
+            None => Vec::new(),
+        };
+        registrable.sort_unstable();
+        registrable.dedup();
+
+        let mut exact = Vec::new();
+        let mut suffix_trie = TrieNode::new();
+        let mut keywords = Vec::new();
+        if let Some(path) = &self.text_path {
+            Self::load_text(path, &mut exact, &mut suffix_trie, &mut keywords)?;
+        }
+
+        exact.sort_unstable();
+        exact.dedup();
+
+        let keyword_set = if keywords.is_empty() {
+            None
+        } else {
+            Some(
+                AhoCorasick::new(&keywords)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+            )
+        };
+
+        let new_rules = RuleSet {
+            exact,
+            registrable,
+            suffix_trie,
+            keyword_set,
+            keywords,
+        };
+
+        let count = new_rules.exact.len() + new_rules.registrable.len();
+        *self.rules.write().unwrap() = new_rules;
+        info!("✅ rule provider reloaded ({} exact/registrable entries)", count);
+        Ok(())
+    }
+
+    /// Checks `domain` against every match mode, reporting which one fired
+    /// first (exact, then the binary registrable-domain list, then suffix,
+    /// then keyword).
+    pub fn is_match(&self, domain: &str) -> Option<RuleMatchKind> {
+        self.rules.read().unwrap().is_match(domain)
+    }
+
+    /// Spawns a background task that calls [`RuleProvider::reload`] every
+    /// `interval`, logging (but not propagating) load failures so a bad
+    /// fetch doesn't take down routing.
+    pub fn spawn_periodic_reload(self: std::sync::Arc<Self>, interval: Duration) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.reload() {
+                    error!("❌ periodic rule provider reload failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Spawns a background task that reloads whenever the process receives
+    /// SIGHUP, matching how Clash-style rule providers refresh on signal.
+    #[cfg(unix)]
+    pub fn spawn_sighup_reload(self: std::sync::Arc<Self>) -> std::io::Result<()> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = signal(SignalKind::hangup())?;
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                info!("🔁 SIGHUP received, reloading rule provider");
+                if let Err(e) = self.reload() {
+                    error!("❌ SIGHUP-triggered rule provider reload failed: {}", e);
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering as AtomicOrdering};
+
+    static NEXT_FILE_ID: AtomicU32 = AtomicU32::new(0);
+
+    fn write_text_rules(lines: &[&str]) -> PathBuf {
+        let id = NEXT_FILE_ID.fetch_add(1, AtomicOrdering::Relaxed);
+        let path = std::env::temp_dir().join(format!("leaf_rule_provider_test_{}.txt", id));
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        path
+    }
+
+    /// Writes a binary site list in `DomainRule`'s format: each entry is the
+    /// registrable domain's u128 encoding, sorted ascending.
+    fn write_binary_rules(registrable_domains: &[&str]) -> PathBuf {
+        let id = NEXT_FILE_ID.fetch_add(1, AtomicOrdering::Relaxed);
+        let path = std::env::temp_dir().join(format!("leaf_rule_provider_test_{}.bin", id));
+        let mut values: Vec<u128> = registrable_domains
+            .iter()
+            .map(|d| registrable_domain_to_u128(d))
+            .collect();
+        values.sort_unstable();
+        let mut data = Vec::with_capacity(values.len() * 16);
+        for v in values {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_binary_matches_subdomains_of_registrable_domain() {
+        let path = write_binary_rules(&["example.com"]);
+        let provider = RuleProvider::new().with_binary(path).unwrap();
+        assert_eq!(provider.is_match("www.example.com"), Some(RuleMatchKind::RegistrableSuffix));
+        assert_eq!(provider.is_match("example.com"), Some(RuleMatchKind::RegistrableSuffix));
+        assert_eq!(provider.is_match("other.com"), None);
+    }
+
+    #[test]
+    fn test_exact_match() {
+        let path = write_text_rules(&["DOMAIN,example.com"]);
+        let provider = RuleProvider::new().with_text(path).unwrap();
+        assert_eq!(provider.is_match("example.com"), Some(RuleMatchKind::Exact));
+        assert_eq!(provider.is_match("sub.example.com"), None);
+    }
+
+    #[test]
+    fn test_suffix_match() {
+        let path = write_text_rules(&["DOMAIN-SUFFIX,example.com"]);
+        let provider = RuleProvider::new().with_text(path).unwrap();
+        assert_eq!(provider.is_match("sub.example.com"), Some(RuleMatchKind::Suffix));
+        assert_eq!(provider.is_match("example.com"), Some(RuleMatchKind::Suffix));
+        assert_eq!(provider.is_match("otherexample.com"), None);
+    }
+
+    #[test]
+    fn test_keyword_match() {
+        let path = write_text_rules(&["DOMAIN-KEYWORD,ads"]);
+        let provider = RuleProvider::new().with_text(path).unwrap();
+        assert_eq!(provider.is_match("ads.example.com"), Some(RuleMatchKind::Keyword));
+        assert_eq!(provider.is_match("example.com"), None);
+    }
+
+    #[test]
+    fn test_reload_picks_up_changes() {
+        let path = write_text_rules(&["DOMAIN,example.com"]);
+        let provider = RuleProvider::new().with_text(path.clone()).unwrap();
+        assert_eq!(provider.is_match("other.com"), None);
+
+        std::fs::write(&path, "DOMAIN,other.com\n").unwrap();
+        provider.reload().unwrap();
+        assert_eq!(provider.is_match("other.com"), Some(RuleMatchKind::Exact));
+        assert_eq!(provider.is_match("example.com"), None);
+    }
+}
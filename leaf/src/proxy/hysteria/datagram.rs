@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use async_trait::async_trait;
 use std::io;
 
@@ -28,7 +31,7 @@ impl OutboundDatagramHandler for Handler {
 
     async fn handle<'a>(
         &'a self,
-        sess: &'a Session,
+        _sess: &'a Session,
         _transport: Option<AnyOutboundTransport>,
     ) -> io::Result<AnyOutboundDatagram> {
         // Create a new UDP session using the hysteria client
@@ -38,14 +41,63 @@ impl OutboundDatagramHandler for Handler {
 
         Ok(Box::new(HysteriaDatagram {
             session: udp_session.clone(),
-            destination: sess.destination.clone(),
+            next_packet_id: AtomicU16::new(0),
+            reassembly: Mutex::new(HashMap::new()),
         }))
     }
 }
 
+/// Largest payload we put in a single QUIC datagram before falling back to
+/// fragmentation. Packets at or under this size are sent exactly as given,
+/// with no framing, so they remain interoperable with an unmodified
+/// Hysteria peer. Only packets above this are split into ordered fragments
+/// and reassembled on the receiving side, since the hysteria session only
+/// exposes whole-datagram send/receive and there's no separate stream
+/// available to carry the fallback out of band.
+const MAX_FRAGMENT_PAYLOAD: usize = 1350;
+/// Marks a datagram as one of our own fragments rather than a raw payload.
+/// Chosen to make an accidental collision with real traffic vanishingly
+/// unlikely; only datagrams produced by fragmenting an oversized packet
+/// carry this prefix, so the common, unfragmented path is never framed.
+const FRAGMENT_MAGIC: [u8; 8] = *b"LeafFrg\0";
+/// magic (8) + packet_id (u16 LE) + frag_index (u8) + frag_count (u8)
+const FRAGMENT_HEADER_LEN: usize = FRAGMENT_MAGIC.len() + 4;
+/// How long an in-progress reassembly may sit incomplete before it's swept
+/// away. Hysteria delivery is unreliable (see `transport_type`), so a
+/// dropped fragment — and the permanently-incomplete entry it leaves behind
+/// — is routine, not exceptional; without this the map would grow without
+/// bound. It also bounds how long a stale entry can stick around to be
+/// silently reused once `packet_id` wraps (it's only a `u16`), which would
+/// otherwise splice fragments from two unrelated packets into one payload.
+const REASSEMBLY_TTL: Duration = Duration::from_secs(10);
+
 struct HysteriaDatagram {
     session: Arc<UdpSession>,
-    destination: SocksAddr,
+    next_packet_id: AtomicU16,
+    reassembly: Mutex<HashMap<u16, Reassembly>>,
+}
+
+/// In-progress fragments for one logical packet, keyed by packet_id.
+struct Reassembly {
+    parts: Vec<Option<Vec<u8>>>,
+    remaining: usize,
+    started_at: Instant,
+}
+
+fn parse_socks_addr(addr: &str) -> io::Result<SocksAddr> {
+    let (host, port) = addr
+        .rsplit_once(':')
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid address format"))?;
+
+    let port = port
+        .parse::<u16>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    Ok(if let Ok(ip) = host.parse::<std::net::IpAddr>() {
+        SocksAddr::from((ip, port))
+    } else {
+        SocksAddr::Domain(host.to_string(), port)
+    })
 }
 
 impl OutboundDatagram for HysteriaDatagram {
@@ -69,42 +121,110 @@ struct DatagramSendHalf(Arc<HysteriaDatagram>);
 #[async_trait]
 impl OutboundDatagramRecvHalf for DatagramRecvHalf {
     async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocksAddr)> {
-        let (data, addr) = self.0.session
-            .receive()
-            .await
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-
-        let n = data.len().min(buf.len());
-        buf[..n].copy_from_slice(&data[..n]);
-
-        let addr = addr.rsplit_once(':')
-            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid address format"))?;
-        
-        let port = addr.1.parse::<u16>()
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-        let socks_addr = if let Ok(ip) = addr.0.parse::<std::net::IpAddr>() {
-            SocksAddr::from((ip, port))
-        } else {
-            SocksAddr::Domain(addr.0.to_string(), port)
-        };
-
-        Ok((n, socks_addr))
+        loop {
+            let (data, addr) = self.0.session
+                .receive()
+                .await
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+            let is_fragment =
+                data.len() >= FRAGMENT_HEADER_LEN && data[..FRAGMENT_MAGIC.len()] == FRAGMENT_MAGIC;
+
+            let complete = if !is_fragment {
+                // No fragment header: this is a whole, unframed packet from
+                // an unmodified peer (or one of our own unfragmented sends).
+                Some(data)
+            } else {
+                let header = &data[FRAGMENT_MAGIC.len()..FRAGMENT_HEADER_LEN];
+                let packet_id = u16::from_le_bytes([header[0], header[1]]);
+                let frag_index = header[2] as usize;
+                let frag_count = (header[3] as usize).max(1);
+                let payload = &data[FRAGMENT_HEADER_LEN..];
+
+                let mut reassembly = self.0.reassembly.lock().unwrap();
+                // Sweep stale incomplete entries before touching this
+                // packet_id, so a wrapped id never reuses a still-open entry
+                // left behind by fragments that never fully arrived.
+                reassembly.retain(|_, entry| entry.started_at.elapsed() < REASSEMBLY_TTL);
+                let entry = reassembly.entry(packet_id).or_insert_with(|| Reassembly {
+                    parts: vec![None; frag_count],
+                    remaining: frag_count,
+                    started_at: Instant::now(),
+                });
+
+                if frag_index < entry.parts.len() && entry.parts[frag_index].is_none() {
+                    entry.parts[frag_index] = Some(payload.to_vec());
+                    entry.remaining -= 1;
+                }
+
+                if entry.remaining == 0 {
+                    let finished = reassembly.remove(&packet_id).unwrap();
+                    Some(finished.parts.into_iter().flatten().flatten().collect())
+                } else {
+                    None
+                }
+            };
+
+            let Some(complete) = complete else {
+                continue;
+            };
+
+            let n = complete.len().min(buf.len());
+            buf[..n].copy_from_slice(&complete[..n]);
+
+            return Ok((n, parse_socks_addr(&addr)?));
+        }
     }
 }
 
 #[async_trait]
 impl OutboundDatagramSendHalf for DatagramSendHalf {
-    async fn send_to(&mut self, buf: &[u8], _target: &SocksAddr) -> io::Result<usize> {
-        self.0.session
-            .send(buf, &self.0.destination.to_string())
-            .await
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-            
+    async fn send_to(&mut self, buf: &[u8], target: &SocksAddr) -> io::Result<usize> {
+        let target_addr = target.to_string();
+
+        if buf.len() <= MAX_FRAGMENT_PAYLOAD {
+            // Common case: send the payload exactly as given, with no
+            // framing, so an unmodified Hysteria peer sees the same bytes
+            // baseline did.
+            self.0.session
+                .send(buf, &target_addr)
+                .await
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+            return Ok(buf.len());
+        }
+
+        // Fragment oversized payloads across multiple datagrams; only these
+        // carry the fragment header, and only a peer that recognizes
+        // FRAGMENT_MAGIC (i.e. another instance of this fallback) will be
+        // able to reassemble them.
+        let frag_count = buf.len().div_ceil(MAX_FRAGMENT_PAYLOAD);
+        if frag_count > u8::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "datagram too large to fragment",
+            ));
+        }
+
+        let packet_id = self.0.next_packet_id.fetch_add(1, Ordering::Relaxed);
+        for (i, chunk) in buf.chunks(MAX_FRAGMENT_PAYLOAD).enumerate() {
+            let mut framed = Vec::with_capacity(FRAGMENT_HEADER_LEN + chunk.len());
+            framed.extend_from_slice(&FRAGMENT_MAGIC);
+            framed.extend_from_slice(&packet_id.to_le_bytes());
+            framed.push(i as u8);
+            framed.push(frag_count as u8);
+            framed.extend_from_slice(chunk);
+
+            self.0.session
+                .send(&framed, &target_addr)
+                .await
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        }
+
         Ok(buf.len())
     }
 
     async fn close(&mut self) -> io::Result<()> {
         Ok(())
     }
-} 
\ No newline at end of file
+}
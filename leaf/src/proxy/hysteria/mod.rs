@@ -1,3 +1,8 @@
+// `outbound` carries TCP (`OutboundStreamHandler`), `datagram` carries UDP
+// (`OutboundDatagramHandler`) over a `HysteriaClient` shared between the
+// two, same split as this crate's other proxies — `outbound::Handler::new`
+// hands back the `Arc<HysteriaClient>` used to construct the matching
+// `datagram::Handler`.
 pub mod outbound;
 pub mod datagram;
 
@@ -112,6 +112,14 @@ impl OutboundStreamHandler for Handler {
 }
 
 impl Handler {
+    /// Builds the TCP-side `Handler`, returning the shared `HysteriaClient`
+    /// alongside it so the caller can build a `datagram::Handler` from the
+    /// same client, the way other proxies in this crate wire up their
+    /// `OutboundStreamHandler`/`OutboundDatagramHandler` pair from one
+    /// underlying connection. UDP-associate (session allocation, datagram
+    /// send/receive, oversized-payload fragmentation) lives entirely in
+    /// `datagram::Handler`, which already implements
+    /// `OutboundDatagramHandler` against this client.
     pub fn new(
         server_ip: String,
         server_port: u16,